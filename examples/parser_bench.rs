@@ -0,0 +1,139 @@
+//! Measures how many requests per second the request parser handles, head
+//! and 1 KiB body, and how many heap allocations it makes per request. It
+//! does so both with a fresh line buffer for each request and with one
+//! buffer reused across requests, as the server does on a connection.
+//! Parsing the same request with extra headers shows the cost of each
+//! header line: its name and value, since every line is read into the same
+//! buffer.
+//!
+//! Run with `cargo run --release --example parser_bench [iterations]`.
+//!
+//! It lives in `examples/` rather than `benches/` because a benchmark
+//! harness would need a dev-dependency or a `[[bench]]` entry in
+//! Cargo.toml, and the built-in `#[bench]` is nightly-only.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use http_server_starter_rust::http::config::ServerConfig;
+use http_server_starter_rust::http::request::HttpRequest;
+
+/// The system allocator, counting allocations.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// A request with ten headers and a 1 KiB body, plus `extra_headers`
+/// headers of the form `X-Extra-N: value`.
+fn representative_request(extra_headers: usize) -> Vec<u8> {
+    let body = "x".repeat(1024);
+    let head = format!(
+        "POST /api/items?page=2 HTTP/1.1\r\n\
+         Host: localhost:4221\r\n\
+         User-Agent: parser-bench/1.0\r\n\
+         Accept: application/json\r\n\
+         Accept-Encoding: gzip, deflate\r\n\
+         Accept-Language: en-US,en;q=0.9\r\n\
+         Cache-Control: no-cache\r\n\
+         Connection: keep-alive\r\n\
+         Content-Type: text/plain\r\n\
+         Cookie: session=0123456789abcdef\r\n\
+         {}\
+         Content-Length: {}\r\n\
+         \r\n",
+        (0..extra_headers)
+            .map(|n| format!("X-Extra-{n}: value\r\n"))
+            .collect::<String>(),
+        body.len()
+    );
+    (head + &body).into_bytes()
+}
+
+/// Parses `input`, head and body, `iterations` times, returning the time
+/// taken and the number of allocations made. With `scratch`, every request
+/// is read through that one line buffer, as on a kept-alive connection.
+fn measure(
+    input: &[u8],
+    iterations: u32,
+    config: &ServerConfig,
+    mut scratch: Option<&mut Vec<u8>>,
+) -> (Duration, u64) {
+    let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..iterations {
+        let mut stream = Cursor::new(black_box(input));
+        let request = match scratch.as_deref_mut() {
+            Some(scratch) => HttpRequest::from_reader_with(&mut stream, config, scratch),
+            None => HttpRequest::from_reader(&mut stream, config),
+        };
+        let mut request = request.expect("benchmark request should parse");
+        request
+            .read_body(&mut stream)
+            .expect("benchmark body should be read");
+        black_box(request);
+    }
+    let elapsed = start.elapsed();
+    (
+        elapsed,
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations_before,
+    )
+}
+
+fn main() {
+    const EXTRA_HEADERS: usize = 50;
+
+    let iterations: u32 = std::env::args()
+        .nth(1)
+        .map(|arg| arg.parse().expect("iterations must be a number"))
+        .unwrap_or(100_000);
+    let input = representative_request(0);
+    let config = ServerConfig::default();
+    let per_request = |allocations: u64| allocations as f64 / f64::from(iterations);
+
+    let (elapsed, allocations) = measure(&input, iterations, &config, None);
+    println!(
+        "parsed {} requests ({} bytes each) in {:.3?}: {:.0} requests/s",
+        iterations,
+        input.len(),
+        elapsed,
+        f64::from(iterations) / elapsed.as_secs_f64()
+    );
+    let fresh = per_request(allocations);
+    println!("{:.1} allocations per request", fresh);
+
+    let mut scratch = Vec::new();
+    let (_, allocations) = measure(&input, iterations, &config, Some(&mut scratch));
+    println!(
+        "{:.1} allocations per request reusing one line buffer",
+        per_request(allocations)
+    );
+
+    let larger = representative_request(EXTRA_HEADERS);
+    let (_, allocations) = measure(&larger, iterations, &config, None);
+    println!(
+        "{:.1} allocations per extra header line",
+        (per_request(allocations) - fresh) / EXTRA_HEADERS as f64
+    );
+}
@@ -1,23 +1,89 @@
+pub mod access_log;
+mod assets;
+pub mod body;
+pub mod client;
+pub mod conditional;
+pub mod config;
+pub mod connection;
+pub mod context;
+pub mod cookie;
+pub mod csrf;
+pub mod date;
+pub mod deserialize;
 pub mod errors;
+pub mod files;
+pub mod json;
+mod listener;
+pub mod metrics;
+mod negotiation;
+pub mod path;
+pub mod pool;
+pub mod proxy;
+pub mod query;
+pub mod range;
+pub mod redirect;
 pub mod request;
 pub mod response;
+pub mod router;
+pub mod server;
+pub mod session;
+mod sha256;
+pub mod shutdown;
+pub mod stats;
+pub mod upgrade;
+pub mod util;
+pub mod writer;
+
+use std::{borrow::Cow, str::FromStr};
 
 use errors::HttpError;
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub enum HttpVersion {
     Http1_0,
     Http1_1,
-    Http2_0,
+    /// A well-formed `HTTP/major.minor` the server does not speak, such as
+    /// `HTTP/2.0`, which only exists as a binary protocol.
+    Unsupported(u8, u8),
 }
 
-impl HttpVersion {
+impl FromStr for HttpVersion {
+    type Err = HttpError;
+
+    /// Parses an `HTTP/major.minor` version with one digit on each side of
+    /// the dot. Anything else is an [`HttpError::UnknownHttpVersion`].
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::HttpVersion;
+    ///
+    /// assert_eq!("HTTP/1.1".parse::<HttpVersion>().unwrap(), HttpVersion::Http1_1);
+    /// assert_eq!("HTTP/3.0".parse::<HttpVersion>().unwrap(), HttpVersion::Unsupported(3, 0));
+    /// assert_eq!("HTTP/1.2".parse::<HttpVersion>().unwrap(), HttpVersion::Unsupported(1, 2));
+    /// assert!("FTP/1.0".parse::<HttpVersion>().is_err());
+    /// assert!("HTTP/1.10".parse::<HttpVersion>().is_err());
+    /// ```
     fn from_str(str: &str) -> Result<Self, HttpError> {
-        match str {
-            "HTTP/1.0" => Ok(Self::Http1_0),
-            "HTTP/1.1" => Ok(Self::Http1_1),
-            "HTTP/2.0" => Ok(Self::Http2_0),
-            _ => Err(HttpError::UnknownHttpVersion(str.to_owned())),
+        let unknown = || HttpError::UnknownHttpVersion(str.to_owned());
+        let Some(&[major, b'.', minor]) = str.strip_prefix("HTTP/").map(str::as_bytes) else {
+            return Err(unknown());
+        };
+        if !major.is_ascii_digit() || !minor.is_ascii_digit() {
+            return Err(unknown());
+        }
+        match (major - b'0', minor - b'0') {
+            (1, 0) => Ok(Self::Http1_0),
+            (1, 1) => Ok(Self::Http1_1),
+            (major, minor) => Ok(Self::Unsupported(major, minor)),
+        }
+    }
+}
+
+impl HttpVersion {
+    pub fn as_str(&self) -> Cow<'static, str> {
+        match self {
+            Self::Http1_0 => Cow::Borrowed("HTTP/1.0"),
+            Self::Http1_1 => Cow::Borrowed("HTTP/1.1"),
+            Self::Unsupported(major, minor) => Cow::Owned(format!("HTTP/{major}.{minor}")),
         }
     }
 }
@@ -0,0 +1,116 @@
+use std::{
+    fmt::Write,
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    date::{civil_from_days, MONTHS},
+    request::HttpRequest,
+    response::HttpResponse,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AccessLogFormat {
+    /// The Common Log Format:
+    /// `host - - [time] "request line" status bytes`.
+    Common,
+    /// The Common Log Format followed by the quoted `Referer` and
+    /// `User-Agent` headers.
+    Combined,
+}
+
+/// Formats one access log line for a request and its response.
+///
+/// ```
+/// use std::{io::Cursor, time::{Duration, UNIX_EPOCH}};
+///
+/// use http_server_starter_rust::http::{
+///     access_log::{format_entry, AccessLogFormat},
+///     config::ServerConfig,
+///     request::HttpRequest,
+///     response::HttpResponse,
+///     HttpVersion,
+/// };
+///
+/// let raw = "GET /a HTTP/1.1\r\nUser-Agent: curl \"8\"\r\n\r\n";
+/// let request = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())?;
+/// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+/// response.write_text("hello");
+/// let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+///
+/// assert_eq!(
+///     format_entry(AccessLogFormat::Combined, &request, &response, None, time),
+///     r#"- - - [14/Nov/2023:22:13:20 +0000] "GET /a HTTP/1.1" 200 5 "-" "curl \"8\"""#
+/// );
+/// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+/// ```
+pub fn format_entry(
+    format: AccessLogFormat,
+    request: &HttpRequest,
+    response: &HttpResponse,
+    peer_addr: Option<SocketAddr>,
+    time: SystemTime,
+) -> String {
+    let client = peer_addr.map_or("-".to_string(), |addr| addr.ip().to_string());
+    let bytes = response
+        .get_body_length()
+        .map_or("-".to_string(), |length| length.to_string());
+    let mut entry = format!(
+        "{} - - [{}] \"{}\" {} {}",
+        client,
+        timestamp(time),
+        escape(&format!(
+            "{} {} {}",
+            request.get_method().as_str(),
+            request.get_path(),
+            request.get_http_version().as_str()
+        )),
+        response.get_status_code(),
+        bytes
+    );
+    if format == AccessLogFormat::Combined {
+        for header in ["Referer", "User-Agent"] {
+            let value = request.get_header(header).unwrap_or("-");
+            let _ = write!(entry, " \"{}\"", escape(value));
+        }
+    }
+    entry
+}
+
+/// Escapes quotes, backslashes and control characters so a value cannot
+/// break out of its quoted log field.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\x{:02x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Formats `time` in UTC as `10/Oct/2000:13:55:36 +0000`.
+fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let seconds_of_day = seconds % 86_400;
+    format!(
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60
+    )
+}
@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use super::{
+    conditional::none_match,
+    range::ranged_response,
+    request::{HttpRequest, HttpRequestMethod},
+    response::{HttpResponse, HttpStatus},
+    sha256::content_tag,
+};
+
+/// Files embedded in the binary, by request path, with their content type.
+#[derive(Default)]
+pub(crate) struct Assets {
+    assets: HashMap<String, Asset>,
+}
+
+struct Asset {
+    data: &'static [u8],
+    content_type: &'static str,
+    etag: String,
+}
+
+impl Assets {
+    pub(crate) fn insert(&mut self, path: String, data: &'static [u8], content_type: &'static str) {
+        let etag = format!("\"{}\"", content_tag(data));
+        self.assets.insert(
+            path,
+            Asset {
+                data,
+                content_type,
+                etag,
+            },
+        );
+    }
+
+    /// Answers a `GET` or `HEAD` for an embedded asset, or returns `None` if
+    /// the request is for something else.
+    pub(crate) fn serve(&self, request: &HttpRequest) -> Option<HttpResponse> {
+        if !matches!(
+            request.get_method(),
+            HttpRequestMethod::Get | HttpRequestMethod::Head
+        ) {
+            return None;
+        }
+        let path = request.get_path();
+        let path = path.split_once('?').map_or(path, |(path, _)| path);
+        let asset = self.assets.get(path)?;
+
+        let mut response = if none_match(request, &asset.etag) {
+            ranged_response(request, asset.data, asset.content_type)
+        } else {
+            HttpResponse::from_status(
+                request.get_http_version().to_owned(),
+                HttpStatus::NotModified,
+            )
+        };
+        response.add_header("ETag", &asset.etag);
+        Some(response)
+    }
+}
@@ -0,0 +1,253 @@
+use std::{
+    io::{self, BufRead, Read},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+use super::{
+    config::ServerConfig,
+    errors::HttpError,
+    request::{find_header, parse_content_length, read_line},
+};
+
+/// Bounds on the lines of a chunked body, so that a client cannot stream
+/// an endless line into memory.
+#[derive(Clone, Copy)]
+pub(crate) struct ChunkLimits {
+    /// The longest chunk size line, including its CRLF.
+    pub(crate) size_line_bytes: usize,
+    /// The most bytes in the trailer section, which is bounded like the
+    /// request head.
+    trailer_bytes: usize,
+    /// The most trailers, bounded like the request's headers.
+    trailers: usize,
+}
+
+impl ChunkLimits {
+    pub(crate) fn new(config: &ServerConfig) -> Self {
+        Self {
+            size_line_bytes: config.max_chunk_size_line_bytes,
+            trailer_bytes: config.max_request_head_bytes,
+            trailers: config.max_headers,
+        }
+    }
+}
+
+/// Tracks how much of a request body is left to read, by `Content-Length`
+/// or chunked transfer coding.
+pub(crate) enum BodyDecoder {
+    Length(u64),
+    Chunked {
+        /// Bytes left in the current chunk; zero when the next chunk size
+        /// line is due.
+        remaining: u64,
+        limits: ChunkLimits,
+    },
+    Done,
+}
+
+impl BodyDecoder {
+    /// Picks the decoder for a request with `headers`, refusing chunked
+    /// bodies whose lines exceed `limits`.
+    ///
+    /// Transfer codings are applied in the order listed, so a request body
+    /// must list `chunked` last, and only once, for its end to be found.
+    /// The codings before it would be removed after dechunking. No
+    /// decompressor is available, so those bodies are refused as
+    /// unsupported rather than passed on still encoded.
+    pub(crate) fn for_headers(
+        headers: &[(String, String)],
+        limits: ChunkLimits,
+    ) -> Result<Self, HttpError> {
+        if let Some(encoding) = find_header(headers, "Transfer-Encoding") {
+            let codings: Vec<&str> = encoding.split(',').map(str::trim).collect();
+            let is_chunked = |coding: &&str| coding.eq_ignore_ascii_case("chunked");
+            let Some((last, others)) = codings.split_last() else {
+                return Err(HttpError::InvalidTransferEncoding(encoding.to_string()));
+            };
+            if !is_chunked(last) || others.iter().any(is_chunked) {
+                return Err(HttpError::InvalidTransferEncoding(encoding.to_string()));
+            }
+            if !others.is_empty() {
+                return Err(HttpError::UnsupportedTransferEncoding(encoding.to_string()));
+            }
+            return Ok(Self::Chunked {
+                remaining: 0,
+                limits,
+            });
+        }
+        match find_header(headers, "Content-Length").map(parse_content_length) {
+            Some(Ok(0)) | None => Ok(Self::Done),
+            Some(Ok(length)) => Ok(Self::Length(length)),
+            Some(Err(e)) => Err(e),
+        }
+    }
+
+    pub(crate) fn is_done(&self) -> bool {
+        matches!(self, Self::Done)
+    }
+
+    /// Reads decoded body bytes from `source` into `buf`, returning `0` once
+    /// the body is complete.
+    pub(crate) fn read(&mut self, source: &mut dyn BufRead, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            match self {
+                Self::Done => return Ok(0),
+                Self::Length(remaining) => {
+                    let read = read_some(source, buf, *remaining)?;
+                    *remaining -= read as u64;
+                    if *remaining == 0 {
+                        *self = Self::Done;
+                    }
+                    return Ok(read);
+                }
+                Self::Chunked {
+                    remaining: 0,
+                    limits,
+                } => match read_chunk_size(source, limits.size_line_bytes)? {
+                    (_, 0) => {
+                        read_trailers(source, *limits, |_| Ok(()))?;
+                        *self = Self::Done;
+                    }
+                    (_, size) => {
+                        *self = Self::Chunked {
+                            remaining: size,
+                            limits: *limits,
+                        }
+                    }
+                },
+                Self::Chunked { remaining, .. } => {
+                    let read = read_some(source, buf, *remaining)?;
+                    *remaining -= read as u64;
+                    if *remaining == 0 {
+                        read_chunk_end(source)?;
+                    }
+                    return Ok(read);
+                }
+            }
+        }
+    }
+}
+
+fn read_some(source: &mut dyn BufRead, buf: &mut [u8], remaining: u64) -> io::Result<usize> {
+    let max = buf
+        .len()
+        .min(usize::try_from(remaining).unwrap_or(usize::MAX));
+    match source.read(&mut buf[..max])? {
+        0 => Err(io::ErrorKind::UnexpectedEof.into()),
+        read => Ok(read),
+    }
+}
+
+/// Reads a chunk size line of at most `max_line_bytes`, including its
+/// CRLF, returning the line without the CRLF and the size it gives. Sizes
+/// that do not fit in a `u64` are refused.
+pub(crate) fn read_chunk_size<R: BufRead + ?Sized>(
+    source: &mut R,
+    max_line_bytes: usize,
+) -> io::Result<(Vec<u8>, u64)> {
+    let (line, _) = read_limited_line(source, max_line_bytes, "Chunk size line is too long")?;
+    let size = std::str::from_utf8(&line)
+        .ok()
+        .and_then(|line| line.split(';').next())
+        .map(str::trim)
+        .filter(|size| !size.is_empty() && size.bytes().all(|byte| byte.is_ascii_hexdigit()))
+        .and_then(|size| u64::from_str_radix(size, 16).ok())
+        .ok_or_else(|| invalid_data("Invalid chunk size"))?;
+    Ok((line, size))
+}
+
+/// Reads the CRLF that ends a chunk's data.
+pub(crate) fn read_chunk_end<R: BufRead + ?Sized>(source: &mut R) -> io::Result<()> {
+    let mut crlf = [0; 2];
+    source.read_exact(&mut crlf)?;
+    if &crlf != b"\r\n" {
+        return Err(invalid_data("Chunk data is not followed by CRLF"));
+    }
+    Ok(())
+}
+
+/// Reads the trailer section that follows the last chunk, through the
+/// blank line that ends it, passing each trailer line to `trailer`.
+/// Sections larger or with more lines than `limits` allow are refused.
+pub(crate) fn read_trailers<R: BufRead + ?Sized>(
+    source: &mut R,
+    limits: ChunkLimits,
+    mut trailer: impl FnMut(&[u8]) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut budget = limits.trailer_bytes;
+    for _ in 0..=limits.trailers {
+        let (line, consumed) = read_limited_line(source, budget, "Trailer section is too large")?;
+        if line.is_empty() {
+            return Ok(());
+        }
+        trailer(&line)?;
+        budget -= consumed;
+    }
+    Err(invalid_data("Too many trailers"))
+}
+
+/// Reads a line of at most `max_bytes`, including its CRLF, returning the
+/// line without the CRLF and the number of bytes consumed.
+fn read_limited_line<R: BufRead + ?Sized>(
+    source: &mut R,
+    max_bytes: usize,
+    too_long: &str,
+) -> io::Result<(Vec<u8>, usize)> {
+    let mut line = Vec::new();
+    source.take(max_bytes as u64).read_until(b'\n', &mut line)?;
+    if line.last() != Some(&b'\n') && line.len() >= max_bytes {
+        return Err(invalid_data(too_long));
+    }
+    let consumed = line.len();
+    let line = read_line(&mut line.as_slice(), &ServerConfig::default())?;
+    Ok((line, consumed))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Reads a request body straight from the connection as the handler asks
+/// for it, decoding chunked transfer coding if the client used it.
+///
+/// Obtained from [`HttpRequest::body_reader`](super::request::HttpRequest::body_reader)
+/// on routes that stream their request bodies.
+pub struct BodyReader {
+    source: Arc<Mutex<dyn BufRead + Send>>,
+    decoder: BodyDecoder,
+    finished: Arc<AtomicBool>,
+}
+
+impl BodyReader {
+    /// Returns the reader and a flag that is set once the whole body has
+    /// been read.
+    pub(crate) fn new(
+        source: Arc<Mutex<dyn BufRead + Send>>,
+        decoder: BodyDecoder,
+    ) -> (Self, Arc<AtomicBool>) {
+        let finished = Arc::new(AtomicBool::new(decoder.is_done()));
+        let reader = Self {
+            source,
+            decoder,
+            finished: Arc::clone(&finished),
+        };
+        (reader, finished)
+    }
+}
+
+impl Read for BodyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut source = self.source.lock().unwrap_or_else(PoisonError::into_inner);
+        let read = self.decoder.read(&mut *source, buf)?;
+        if self.decoder.is_done() {
+            self.finished.store(true, Ordering::Release);
+        }
+        Ok(read)
+    }
+}
@@ -0,0 +1,124 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+};
+
+use super::{
+    config::ServerConfig,
+    errors::HttpError,
+    request::{parse_content_length, read_headers, HttpRequestLine, HttpRequestMethod},
+    response::HttpResponseStatusLine,
+    HttpVersion,
+};
+
+/// A response received by [`HttpClient`].
+pub struct ParsedResponse {
+    status_line: HttpResponseStatusLine,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl ParsedResponse {
+    pub fn from_reader<R: BufRead>(stream: &mut R) -> Result<Self, HttpError> {
+        let mut response = Self {
+            status_line: HttpResponseStatusLine::from_stream(stream)?,
+            headers: read_headers(stream, &ServerConfig::default())?,
+            body: Vec::new(),
+        };
+
+        match response.get_header("Content-Length") {
+            Some(length) => {
+                let length = usize::try_from(parse_content_length(length)?)
+                    .map_err(|_| HttpError::InvalidContentLength(length.to_string()))?;
+                response.body.resize(length, 0);
+                stream.read_exact(&mut response.body)?;
+            }
+            None => {
+                stream.read_to_end(&mut response.body)?;
+            }
+        }
+        Ok(response)
+    }
+
+    pub fn get_header(&self, header_name: &str) -> Option<&String> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(header_name))
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    pub fn get_status_code(&self) -> u16 {
+        self.status_line.get_status_code()
+    }
+
+    pub fn get_reason(&self) -> &str {
+        self.status_line.get_reason()
+    }
+
+    pub fn get_http_version(&self) -> &HttpVersion {
+        self.status_line.get_http_version()
+    }
+
+    pub fn get_body(&self) -> &[u8] {
+        &self.body
+    }
+}
+
+/// A minimal blocking HTTP/1.1 client.
+///
+/// Only `http://` URLs are supported, and each request uses a fresh
+/// connection that is closed once the response has been read.
+pub struct HttpClient;
+
+impl HttpClient {
+    pub fn get(url: &str) -> Result<ParsedResponse, HttpError> {
+        let (authority, path) = split_url(url)?;
+        let stream = TcpStream::connect(with_default_port(authority))?;
+
+        let request_line = HttpRequestLine::new(
+            HttpVersion::Http1_1,
+            path.to_string(),
+            HttpRequestMethod::Get,
+        );
+        let headers = [("Host", authority), ("Connection", "close")];
+
+        let mut request = format!("{request_line}\r\n");
+        for (name, value) in headers {
+            request += &format!("{name}: {value}\r\n");
+        }
+        request += "\r\n";
+        (&stream).write_all(request.as_bytes())?;
+
+        ParsedResponse::from_reader(&mut BufReader::new(&stream))
+    }
+}
+
+/// Splits an `http://` URL into its authority (`host[:port]`) and path.
+pub(crate) fn split_url(url: &str) -> Result<(&str, &str), HttpError> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| HttpError::InvalidUrl(url.to_string()))?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return Err(HttpError::InvalidUrl(url.to_string()));
+    }
+    Ok((authority, path))
+}
+
+pub(crate) fn with_default_port(authority: &str) -> String {
+    let has_port = authority
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| !port.contains(']'));
+    if has_port {
+        authority.to_string()
+    } else {
+        format!("{authority}:80")
+    }
+}
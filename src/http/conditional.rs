@@ -0,0 +1,190 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{
+    date::parse_http_date,
+    request::{HttpRequest, HttpRequestMethod},
+    response::{HttpResponse, HttpStatus},
+};
+
+/// Evaluates the `If-Match` and `If-Unmodified-Since` preconditions of a
+/// state-changing request against the resource's current entity tag and
+/// modification time, either of which is `None` when the resource has none
+/// or does not exist.
+///
+/// Returns a `412 Precondition Failed` response for the handler to send
+/// when a precondition does not hold. As RFC 7232 specifies,
+/// `If-Unmodified-Since` is ignored when `If-Match` is present.
+///
+/// ```
+/// use std::{io::Cursor, time::{Duration, UNIX_EPOCH}};
+///
+/// use http_server_starter_rust::http::{
+///     conditional::check_preconditions, config::ServerConfig, request::HttpRequest,
+/// };
+///
+/// let request = |header: &str, value: &str| {
+///     let raw = format!("PUT /doc HTTP/1.1\r\n{header}: {value}\r\n\r\n");
+///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default()).unwrap()
+/// };
+///
+/// let put = |if_match: &str| request("If-Match", if_match);
+/// assert!(check_preconditions(&put("\"v1\", \"v2\""), Some("\"v2\""), None).is_ok());
+/// let failed = check_preconditions(&put("\"v1\""), Some("\"v2\""), None).unwrap_err();
+/// assert_eq!(failed.get_status_code(), 412);
+/// // Weak tags never match in If-Match.
+/// assert!(check_preconditions(&put("W/\"v2\""), Some("W/\"v2\""), None).is_err());
+///
+/// let since = request("If-Unmodified-Since", "Sun, 06 Nov 1994 08:49:37 GMT");
+/// let modified = |seconds| Some(UNIX_EPOCH + Duration::from_secs(seconds));
+/// assert!(check_preconditions(&since, None, modified(784_111_777)).is_ok());
+/// assert!(check_preconditions(&since, None, modified(784_111_778)).is_err());
+/// ```
+pub fn check_preconditions(
+    request: &HttpRequest,
+    etag: Option<&str>,
+    last_modified: Option<SystemTime>,
+) -> Result<(), HttpResponse> {
+    let holds = match request.get_header("If-Match") {
+        Some(if_match) => if_match_holds(if_match, etag),
+        None => match request
+            .get_header("If-Unmodified-Since")
+            .and_then(parse_http_date)
+        {
+            Some(since) => last_modified.is_some_and(|modified| truncate(modified) <= since),
+            None => true,
+        },
+    };
+    if holds {
+        return Ok(());
+    }
+    Err(HttpResponse::from_status(
+        request.get_http_version().to_owned(),
+        HttpStatus::PreconditionFailed,
+    ))
+}
+
+/// Whether the `If-None-Match` precondition of a `GET` or `HEAD` holds
+/// for a resource with `etag`, meaning the client's cached copy is stale
+/// and the resource should be sent. When it does not hold the handler
+/// answers `304 Not Modified`.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use http_server_starter_rust::http::{
+///     conditional::none_match, config::ServerConfig, request::HttpRequest,
+/// };
+///
+/// let get = |headers: &str| {
+///     let raw = format!("GET /app.js HTTP/1.1\r\n{headers}\r\n");
+///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default()).unwrap()
+/// };
+///
+/// assert!(none_match(&get(""), "\"v2\""));
+/// assert!(none_match(&get("If-None-Match: \"v1\"\r\n"), "\"v2\""));
+/// assert!(!none_match(&get("If-None-Match: \"v1\", \"v2\"\r\n"), "\"v2\""));
+/// // If-None-Match uses the weak comparison.
+/// assert!(!none_match(&get("If-None-Match: W/\"v2\"\r\n"), "\"v2\""));
+/// assert!(!none_match(&get("If-None-Match: *\r\n"), "\"v2\""));
+/// ```
+pub fn none_match(request: &HttpRequest, etag: &str) -> bool {
+    let Some(if_none_match) = request.get_header("If-None-Match") else {
+        return true;
+    };
+    if if_none_match.trim() == "*" {
+        return false;
+    }
+    let opaque = |tag: &str| tag.strip_prefix("W/").unwrap_or(tag).to_string();
+    !if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| opaque(candidate) == opaque(etag))
+}
+
+/// Turns a `200 OK` answering a `GET` or `HEAD` into a `304 Not Modified`
+/// if the client's cached copy is still current, going by the `ETag` and
+/// `Last-Modified` headers the response carries. Returns whether it did.
+/// As RFC 7232 specifies, `If-Modified-Since` is ignored when
+/// `If-None-Match` is present.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use http_server_starter_rust::http::{
+///     conditional::revalidate, config::ServerConfig, request::HttpRequest,
+///     response::HttpResponse, HttpVersion,
+/// };
+///
+/// let status = |headers: &str| {
+///     let raw = format!("GET /doc HTTP/1.1\r\n{headers}\r\n");
+///     let request =
+///         HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default()).unwrap();
+///     let mut response = HttpResponse::new(HttpVersion::Http1_1);
+///     response
+///         .add_header("ETag", "\"v2\"")
+///         .add_header("Last-Modified", "Sun, 06 Nov 1994 08:49:37 GMT")
+///         .write_text("document");
+///     revalidate(&request, &mut response);
+///     response.get_status_code()
+/// };
+///
+/// assert_eq!(status(""), 200);
+/// assert_eq!(status("If-None-Match: \"v2\"\r\n"), 304);
+/// assert_eq!(status("If-None-Match: \"v1\"\r\n"), 200);
+/// assert_eq!(status("If-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n"), 304);
+/// assert_eq!(status("If-Modified-Since: Sun, 06 Nov 1994 08:49:36 GMT\r\n"), 200);
+/// assert_eq!(
+///     status("If-None-Match: \"v1\"\r\nIf-Modified-Since: Sun, 06 Nov 1994 08:49:37 GMT\r\n"),
+///     200
+/// );
+/// ```
+pub fn revalidate(request: &HttpRequest, response: &mut HttpResponse) -> bool {
+    if !matches!(
+        request.get_method(),
+        HttpRequestMethod::Get | HttpRequestMethod::Head
+    ) || response.get_status_code() != HttpStatus::Ok.code()
+    {
+        return false;
+    }
+    let current = match request.get_header("If-None-Match") {
+        Some(_) => response
+            .get_header("ETag")
+            .is_some_and(|etag| !none_match(request, etag)),
+        None => {
+            let since = request
+                .get_header("If-Modified-Since")
+                .and_then(parse_http_date);
+            let modified = response
+                .get_header("Last-Modified")
+                .and_then(parse_http_date);
+            matches!((since, modified), (Some(since), Some(modified)) if modified <= since)
+        }
+    };
+    if current {
+        response.make_not_modified();
+    }
+    current
+}
+
+fn if_match_holds(if_match: &str, etag: Option<&str>) -> bool {
+    let Some(etag) = etag else {
+        return false;
+    };
+    if if_match.trim() == "*" {
+        return true;
+    }
+    // If-Match uses the strong comparison, so weak tags never match.
+    !etag.starts_with("W/")
+        && if_match
+            .split(',')
+            .map(str::trim)
+            .any(|candidate| candidate == etag)
+}
+
+/// HTTP dates have a resolution of one second.
+fn truncate(time: SystemTime) -> SystemTime {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
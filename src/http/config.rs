@@ -0,0 +1,514 @@
+use std::{collections::HashMap, time::Duration};
+
+use super::access_log::AccessLogFormat;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineEndings {
+    /// Lines must end with `\r\n`; a bare `\n` is rejected.
+    Strict,
+    /// A bare `\n` also ends a line, with an optional preceding `\r` stripped.
+    Lenient,
+}
+
+/// What to do with obsolete line folding, a header line starting with a
+/// space or tab that continues the previous header's value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineFolding {
+    /// Reject the message, as RFC 7230 allows.
+    Reject,
+    /// Join the continuation onto the previous value with a single space.
+    Unfold,
+}
+
+/// A body for responses the server generates itself, such as a `404` for an
+/// unrouted path or a `400` for a malformed request.
+#[derive(Clone, Debug)]
+pub struct ErrorPage {
+    pub content_type: String,
+    /// The body, with `{status}` and `{reason}` replaced by the response's
+    /// status code and reason phrase.
+    pub template: String,
+}
+
+impl ErrorPage {
+    pub fn text(template: &str) -> Self {
+        Self {
+            content_type: "text/plain".to_string(),
+            template: template.to_string(),
+        }
+    }
+
+    pub fn html(template: &str) -> Self {
+        Self {
+            content_type: "text/html".to_string(),
+            template: template.to_string(),
+        }
+    }
+
+    /// ```
+    /// use http_server_starter_rust::http::config::ErrorPage;
+    ///
+    /// let page = ErrorPage::html("<h1>{status} {reason}</h1>");
+    /// assert_eq!(page.render(404, "Not Found"), "<h1>404 Not Found</h1>");
+    /// ```
+    pub fn render(&self, status_code: u16, reason: &str) -> String {
+        self.template
+            .replace("{status}", &status_code.to_string())
+            .replace("{reason}", reason)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    /// Which line terminators the request line and headers may use.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::{LineEndings, ServerConfig},
+    ///     errors::HttpError,
+    ///     request::HttpRequest,
+    /// };
+    ///
+    /// let raw = "GET /bare HTTP/1.1\nHost: example.com\r\nAccept: */*\n\n";
+    /// let parse = |line_endings| {
+    ///     let config = ServerConfig {
+    ///         line_endings,
+    ///         ..ServerConfig::default()
+    ///     };
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &config)
+    /// };
+    ///
+    /// let rejected = parse(LineEndings::Strict).err().unwrap();
+    /// assert!(matches!(rejected, HttpError::BareLineFeed(_)));
+    /// assert_eq!(rejected.status().code(), 400);
+    /// let accepted = parse(LineEndings::Lenient)?;
+    /// assert_eq!(accepted.get_path(), "/bare");
+    /// assert_eq!(accepted.get_host(), Some("example.com"));
+    /// assert_eq!(accepted.get_header("Accept").unwrap(), "*/*");
+    /// # Ok::<(), HttpError>(())
+    /// ```
+    pub line_endings: LineEndings,
+    /// How folded header lines are handled. A folded line before any
+    /// header has nothing to continue and is always rejected.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::{LineFolding, ServerConfig},
+    ///     request::HttpRequest,
+    /// };
+    ///
+    /// let raw = "GET / HTTP/1.1\r\nX-Note: first\r\n \t second\r\nHost: example.com\r\n\r\n";
+    /// let parse = |line_folding| {
+    ///     let config = ServerConfig {
+    ///         line_folding,
+    ///         ..ServerConfig::default()
+    ///     };
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &config)
+    /// };
+    ///
+    /// let rejected = parse(LineFolding::Reject).err().unwrap();
+    /// assert_eq!(rejected.status().code(), 400);
+    /// let unfolded = parse(LineFolding::Unfold)?;
+    /// assert_eq!(unfolded.get_header("X-Note").unwrap(), "first second");
+    /// assert_eq!(unfolded.get_host(), Some("example.com"));
+    /// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+    /// ```
+    pub line_folding: LineFolding,
+    /// Capacity of the buffer responses are written through.
+    pub write_buffer_size: usize,
+    /// Upper bound on the combined size of the request line and headers,
+    /// including line terminators. Larger requests are answered with `431`.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// };
+    ///
+    /// let config = ServerConfig {
+    ///     max_request_head_bytes: 1024,
+    ///     max_headers: 1000,
+    ///     ..ServerConfig::default()
+    /// };
+    /// let parse = |headers: usize| {
+    ///     // Each of these headers is 10 bytes with its CRLF.
+    ///     let raw = format!("GET / HTTP/1.1\r\n{}\r\n", "X-Ab: 12\r\n".repeat(headers));
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &config)
+    /// };
+    ///
+    /// assert!(parse(90).is_ok());
+    /// // No one header is large, but together they pass the limit.
+    /// let too_large = parse(110).err().unwrap();
+    /// assert!(matches!(too_large, HttpError::RequestHeadTooLarge));
+    /// assert_eq!(too_large.status().code(), 431);
+    /// ```
+    pub max_request_head_bytes: usize,
+    /// Upper bound on the number of header lines in a message. Requests with
+    /// more are answered with `431`.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// };
+    ///
+    /// let config = ServerConfig {
+    ///     max_headers: 4,
+    ///     ..ServerConfig::default()
+    /// };
+    /// let parse = |headers: usize| {
+    ///     let raw = format!("GET / HTTP/1.1\r\n{}\r\n", "X-Ab: 12\r\n".repeat(headers));
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &config)
+    /// };
+    ///
+    /// assert!(parse(4).is_ok());
+    /// let too_many = parse(5).err().unwrap();
+    /// assert!(matches!(too_many, HttpError::TooManyHeaders));
+    /// assert_eq!(too_many.status().code(), 431);
+    /// ```
+    pub max_headers: usize,
+    /// Upper bound on the length of a chunk size line in a chunked body,
+    /// including any chunk extensions and the line terminator. Longer
+    /// lines, like sizes that overflow a `u64`, make the body unreadable
+    /// and the request is answered with `400`. The trailer section after
+    /// the last chunk is bounded by
+    /// [`max_request_head_bytes`](Self::max_request_head_bytes) and
+    /// [`max_headers`](Self::max_headers) in the same way.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let read_body = |chunks: &str| {
+    ///     let raw = format!("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n{chunks}");
+    ///     let mut stream = Cursor::new(raw);
+    ///     let mut request = HttpRequest::from_reader(&mut stream, &ServerConfig::default())?;
+    ///     request.read_body(&mut stream)
+    /// };
+    ///
+    /// assert!(read_body("5\r\nhello\r\n0\r\n\r\n").is_ok());
+    /// let overflowing = read_body("10000000000000000\r\n").unwrap_err();
+    /// assert_eq!(overflowing.status().code(), 400);
+    /// let overlong = read_body(&format!("5;{}\r\nhello\r\n0\r\n\r\n", "x".repeat(2000)));
+    /// assert_eq!(overlong.unwrap_err().status().code(), 400);
+    ///
+    /// let unterminated = read_body(&format!("5\r\nhello{}", "x".repeat(100_000)));
+    /// assert_eq!(unterminated.unwrap_err().status().code(), 400);
+    /// assert!(read_body("0\r\nX-Checksum: 1\r\n\r\n").is_ok());
+    /// let huge_trailer = read_body(&format!("0\r\nX: {}\r\n\r\n", "x".repeat(10_000)));
+    /// assert_eq!(huge_trailer.unwrap_err().status().code(), 400);
+    /// let many_trailers = read_body(&format!("0\r\n{}\r\n", "X: 1\r\n".repeat(101)));
+    /// assert_eq!(many_trailers.unwrap_err().status().code(), 400);
+    /// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+    /// ```
+    pub max_chunk_size_line_bytes: usize,
+    /// Collapses runs of slashes in the request path into one before the
+    /// path is canonicalized and routed, so that `/a//b` matches a route
+    /// for `/a/b`. Off by default, leaving such paths as sent.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, request::HttpRequest, response::HttpResponse, router::Router,
+    /// };
+    ///
+    /// let mut router = Router::new();
+    /// router.add(None, "/echo/foo", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// let route = |merge_slashes| {
+    ///     let config = ServerConfig {
+    ///         merge_slashes,
+    ///         ..ServerConfig::default()
+    ///     };
+    ///     let raw = "GET /echo//foo?next=//x HTTP/1.1\r\n\r\n";
+    ///     let request = HttpRequest::from_reader(&mut Cursor::new(raw), &config).unwrap();
+    ///     (request.get_path().to_string(), router.find(&request).is_some())
+    /// };
+    ///
+    /// assert_eq!(route(false), ("/echo//foo?next=//x".to_string(), false));
+    /// assert_eq!(route(true), ("/echo/foo?next=//x".to_string(), true));
+    /// ```
+    pub merge_slashes: bool,
+    /// Number of worker threads connections are handled on.
+    pub worker_threads: usize,
+    /// Workers are named `{worker_name_prefix}-N`, which shows up in panic
+    /// messages and debuggers.
+    pub worker_name_prefix: String,
+    /// The most accepted connections left waiting for a free worker. Any
+    /// more are turned away with `503 Service Unavailable` and a
+    /// `Retry-After` of [`overload_retry_after`](Self::overload_retry_after),
+    /// instead of queueing behind the rest. `None` queues every connection.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    ///     time::Duration,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let config = ServerConfig {
+    ///     worker_threads: 1,
+    ///     max_queued_connections: Some(1),
+    ///     ..ServerConfig::default()
+    /// };
+    /// let mut server = Server::bind("127.0.0.1:0", config)?;
+    /// server.get("/slow", |context| {
+    ///     thread::sleep(Duration::from_millis(300));
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// let addr = server.local_addr()?;
+    /// let stats = server.stats_handle();
+    /// thread::spawn(move || server.run());
+    ///
+    /// let send = || -> std::io::Result<TcpStream> {
+    ///     let mut stream = TcpStream::connect(addr)?;
+    ///     stream.write_all(b"GET /slow HTTP/1.1\r\nConnection: close\r\n\r\n")?;
+    ///     Ok(stream)
+    /// };
+    /// // One connection keeps the worker busy and the next one waits for it.
+    /// let mut running = send()?;
+    /// while stats.snapshot().active_connections == 0 {
+    ///     thread::sleep(Duration::from_millis(5));
+    /// }
+    /// let mut queued = send()?;
+    /// while stats.snapshot().connections_accepted < 2 {
+    ///     thread::sleep(Duration::from_millis(5));
+    /// }
+    ///
+    /// // The response does not wait for a request.
+    /// let mut turned_away = TcpStream::connect(addr)?;
+    /// let mut received = String::new();
+    /// turned_away.read_to_string(&mut received)?;
+    /// assert!(received.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+    /// assert!(received.contains("\r\nRetry-After: 1\r\n"));
+    ///
+    /// for stream in [&mut running, &mut queued] {
+    ///     let mut received = String::new();
+    ///     stream.read_to_string(&mut received)?;
+    ///     assert!(received.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub max_queued_connections: Option<usize>,
+    /// How long clients turned away by
+    /// [`max_queued_connections`](Self::max_queued_connections) are asked
+    /// to wait before trying again, sent in whole seconds.
+    pub overload_retry_after: Duration,
+    /// Whether connections are kept open for further requests.
+    pub keep_alive: bool,
+    /// Whether HTTP/1.0 clients asking for `Connection: keep-alive` get it.
+    /// Turning this off closes every HTTP/1.0 connection after its first
+    /// response, for old clients that mishandle keep-alive; HTTP/1.1 is
+    /// unaffected.
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpStream;
+    /// use std::thread;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let config = ServerConfig {
+    ///     keep_alive_http1_0: false,
+    ///     ..ServerConfig::default()
+    /// };
+    /// let mut server = Server::bind("127.0.0.1:0", config)?;
+    /// server.get("/", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// stream.write_all(b"GET / HTTP/1.0\r\nConnection: keep-alive\r\n\r\n")?;
+    /// // Reading to the end only returns once the server has closed.
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response)?;
+    /// assert!(response.contains("\r\nConnection: close\r\n"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub keep_alive_http1_0: bool,
+    /// How long a kept-alive connection may sit idle before it is closed.
+    /// An idle connection keeps its worker thread busy until then.
+    pub idle_timeout: Duration,
+    /// The most requests served on one connection before it is closed.
+    /// Kept-alive responses advertise this and
+    /// [`idle_timeout`](Self::idle_timeout) in a `Keep-Alive` header, with
+    /// `max` counting the requests left.
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpStream;
+    /// use std::thread;
+    /// use std::time::Duration;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let config = ServerConfig {
+    ///     idle_timeout: Duration::from_secs(7),
+    ///     max_requests_per_connection: 3,
+    ///     ..ServerConfig::default()
+    /// };
+    /// let mut server = Server::bind("127.0.0.1:0", config)?;
+    /// server.get("/", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// stream.write_all("GET / HTTP/1.1\r\n\r\n".repeat(3).as_bytes())?;
+    /// let mut responses = String::new();
+    /// stream.read_to_string(&mut responses)?;
+    /// let responses: Vec<&str> = responses.split_inclusive("\r\n\r\n").collect();
+    /// assert_eq!(responses.len(), 3);
+    /// assert!(responses[0].contains("\r\nKeep-Alive: timeout=7, max=2\r\n"));
+    /// assert!(responses[1].contains("\r\nKeep-Alive: timeout=7, max=1\r\n"));
+    /// assert!(responses[2].contains("\r\nConnection: close\r\n"));
+    /// assert!(!responses[2].contains("Keep-Alive"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub max_requests_per_connection: usize,
+    /// The most identical requests, by method and target, served back to
+    /// back on one connection. The one after is logged and gets the last
+    /// response on the connection, as a guard against clients stuck
+    /// repeating themselves. `None` allows any number.
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpStream;
+    /// use std::thread;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let config = ServerConfig {
+    ///     max_repeated_requests: Some(2),
+    ///     ..ServerConfig::default()
+    /// };
+    /// let mut server = Server::bind("127.0.0.1:0", config)?;
+    /// server.get("/", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// stream.write_all("GET / HTTP/1.1\r\n\r\n".repeat(4).as_bytes())?;
+    /// let mut received = String::new();
+    /// stream.read_to_string(&mut received)?;
+    /// let responses: Vec<&str> = received.split_inclusive("\r\n\r\n").collect();
+    /// assert_eq!(responses.len(), 3);
+    /// assert!(responses[1].contains("\r\nConnection: keep-alive\r\n"));
+    /// assert!(responses[2].contains("\r\nConnection: close\r\n"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub max_repeated_requests: Option<usize>,
+    /// How long handlers have to respond, for routes without a
+    /// [`Route::with_timeout`](super::router::Route::with_timeout) of their
+    /// own. `None` lets handlers run for as long as they like.
+    pub handler_timeout: Option<Duration>,
+    /// Streams request bodies on every route, as if each had been set up
+    /// with [`Route::with_streaming_body`](super::router::Route::with_streaming_body).
+    pub stream_request_bodies: bool,
+    /// Redirects requests sent with `Upgrade-Insecure-Requests: 1` to
+    /// HTTPS before routing them, for a server that is the plain HTTP side
+    /// of a deployment also serving HTTPS. See
+    /// [`upgrade_insecure`](super::redirect::upgrade_insecure).
+    pub upgrade_insecure_requests: bool,
+    /// Sends status lines without a reason phrase, such as `HTTP/1.1 200 `;
+    /// see [`HttpResponse::omit_reason_phrase`](super::response::HttpResponse::omit_reason_phrase).
+    pub omit_reason_phrase: bool,
+    /// Sends response header names in their canonical casing, whatever
+    /// casing handlers used; see
+    /// [`HttpResponse::canonicalize_header_names`](super::response::HttpResponse::canonicalize_header_names).
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let config = ServerConfig {
+    ///     canonical_header_names: true,
+    ///     ..ServerConfig::default()
+    /// };
+    /// let mut server = Server::bind("127.0.0.1:0", config)?;
+    /// server.get("/", |context| {
+    ///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///     response
+    ///         .add_header("content-type", "text/plain")
+    ///         .add_content("hi");
+    ///     response
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// stream.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")?;
+    /// let mut received = String::new();
+    /// stream.read_to_string(&mut received)?;
+    /// assert!(received.contains("\r\nContent-Type: text/plain\r\n"));
+    /// assert!(!received.contains("content-type"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub canonical_header_names: bool,
+    pub access_log_format: AccessLogFormat,
+    /// Bodies for error responses, by status code, used when the response
+    /// has no body of its own.
+    pub error_pages: HashMap<u16, ErrorPage>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            line_endings: LineEndings::Strict,
+            line_folding: LineFolding::Reject,
+            write_buffer_size: 8 * 1024,
+            max_request_head_bytes: 8 * 1024,
+            max_headers: 100,
+            max_chunk_size_line_bytes: 1024,
+            merge_slashes: false,
+            worker_threads: 16,
+            worker_name_prefix: "http-worker".to_string(),
+            max_queued_connections: None,
+            overload_retry_after: Duration::from_secs(1),
+            keep_alive: true,
+            keep_alive_http1_0: true,
+            idle_timeout: Duration::from_secs(5),
+            max_requests_per_connection: 100,
+            max_repeated_requests: None,
+            handler_timeout: None,
+            stream_request_bodies: false,
+            upgrade_insecure_requests: false,
+            omit_reason_phrase: false,
+            canonical_header_names: false,
+            access_log_format: AccessLogFormat::Common,
+            error_pages: HashMap::new(),
+        }
+    }
+}
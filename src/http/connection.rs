@@ -0,0 +1,85 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// A buffered, bidirectional client connection.
+///
+/// Reads and writes share one underlying stream, which may be a `TcpStream`
+/// or any other `Read + Write` transport such as a TLS session or an
+/// in-memory pipe. Buffered writes are sent when the buffer fills up, on
+/// [`flush`](Write::flush), and when the connection is dropped.
+pub struct Connection<S: Read + Write> {
+    reader: BufReader<S>,
+    write_buffer: Vec<u8>,
+    write_buffer_size: usize,
+}
+
+impl<S: Read + Write> Connection<S> {
+    pub fn new(stream: S, write_buffer_size: usize) -> Self {
+        Self {
+            reader: BufReader::new(stream),
+            write_buffer: Vec::with_capacity(write_buffer_size),
+            write_buffer_size,
+        }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        self.reader.get_ref()
+    }
+
+    /// The underlying stream. Writing to it directly skips the write
+    /// buffer, which has to be flushed first.
+    pub(crate) fn get_mut(&mut self) -> &mut S {
+        self.reader.get_mut()
+    }
+
+    /// The bytes received but not read yet, without waiting for more.
+    pub fn buffered(&self) -> &[u8] {
+        self.reader.buffer()
+    }
+
+    fn flush_buffer(&mut self) -> io::Result<()> {
+        self.reader.get_mut().write_all(&self.write_buffer)?;
+        self.write_buffer.clear();
+        Ok(())
+    }
+}
+
+impl<S: Read + Write> Read for Connection<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+impl<S: Read + Write> BufRead for Connection<S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.reader.consume(amt)
+    }
+}
+
+impl<S: Read + Write> Write for Connection<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.write_buffer.len() + buf.len() > self.write_buffer_size {
+            self.flush_buffer()?;
+        }
+        if buf.len() >= self.write_buffer_size {
+            self.reader.get_mut().write(buf)
+        } else {
+            self.write_buffer.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_buffer()?;
+        self.reader.get_mut().flush()
+    }
+}
+
+impl<S: Read + Write> Drop for Connection<S> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
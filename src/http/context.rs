@@ -0,0 +1,73 @@
+use std::{
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use super::{request::HttpRequest, router::Params, session::Session};
+
+/// Everything a handler is given about the request it is answering.
+///
+/// ```
+/// use std::{
+///     io::{Read, Write},
+///     net::TcpStream,
+///     thread,
+/// };
+///
+/// use http_server_starter_rust::http::{
+///     config::ServerConfig, response::HttpResponse, server::Server,
+/// };
+///
+/// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+/// server.get("/users/:id", |context| {
+///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+///     response.write_text(&format!(
+///         "user {} as {} for {}, request {}",
+///         context.params.get("id").unwrap_or_default(),
+///         context.get_query("format").unwrap_or("text"),
+///         context.peer_addr.map_or("?".to_string(), |addr| addr.ip().to_string()),
+///         context.request_id,
+///     ));
+///     response
+/// });
+/// let addr = server.local_addr()?;
+/// thread::spawn(move || server.run());
+///
+/// let mut stream = TcpStream::connect(addr)?;
+/// stream.write_all(b"GET /users/42?format=json HTTP/1.1\r\nConnection: close\r\n\r\n")?;
+/// let mut received = String::new();
+/// stream.read_to_string(&mut received)?;
+/// assert!(received.ends_with("\r\n\r\nuser 42 as json for 127.0.0.1, request 1"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct RequestContext<'a> {
+    pub request: &'a HttpRequest,
+    /// The values the route pattern captured from the path.
+    pub params: &'a Params,
+    /// The client's address, which connections over a Unix domain socket
+    /// do not have.
+    pub peer_addr: Option<SocketAddr>,
+    /// Counts up from 1 across the requests a server passes to handlers.
+    pub request_id: u64,
+    /// The client's session, on a server set up with
+    /// [`Server::sessions`](super::server::Server::sessions).
+    pub session: Option<&'a Session>,
+}
+
+impl RequestContext<'_> {
+    /// The first value of `key` in the query string.
+    pub fn get_query(&self, key: &str) -> Option<&str> {
+        self.request.get_query(key)
+    }
+
+    /// When the handler has to respond by; see
+    /// [`HttpRequest::deadline`].
+    pub fn deadline(&self) -> Option<Instant> {
+        self.request.deadline()
+    }
+
+    /// See [`HttpRequest::time_remaining`].
+    pub fn time_remaining(&self) -> Option<Duration> {
+        self.request.time_remaining()
+    }
+}
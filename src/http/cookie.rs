@@ -0,0 +1,79 @@
+use std::fmt::Write;
+
+use super::{
+    request::HttpRequest, response::HttpResponse, sha256::hmac_sha256, util::constant_time_eq,
+};
+
+/// Signs cookie values with a secret key, so that a client cannot change
+/// them without the server noticing. Values are signed, not encrypted:
+/// the client can still read them.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use http_server_starter_rust::http::{
+///     config::ServerConfig, cookie::SecureCookies, request::HttpRequest,
+///     response::HttpResponse, HttpVersion,
+/// };
+///
+/// let cookies = SecureCookies::new(b"server secret");
+/// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+/// cookies.set_signed_cookie(&mut response, "user", "ada");
+/// let set_cookie = response.get_header("Set-Cookie").unwrap();
+/// assert_eq!(
+///     set_cookie,
+///     "user=ada.34624d3cc60af56a698bcb2c565519ce21f285b6efffef94550a24cb451c8bec; Path=/; HttpOnly"
+/// );
+///
+/// let read = |cookie: &str| {
+///     let raw = format!("GET / HTTP/1.1\r\nCookie: {cookie}\r\n\r\n");
+///     let request =
+///         HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default()).unwrap();
+///     cookies.get_signed_cookie(&request, "user").map(String::from)
+/// };
+/// let signed = set_cookie.split(';').next().unwrap();
+/// assert_eq!(read(signed).as_deref(), Some("ada"));
+/// assert_eq!(read(&signed.replace("ada", "bob")), None);
+/// assert_eq!(read("user=ada"), None);
+/// ```
+#[derive(Clone)]
+pub struct SecureCookies {
+    key: Vec<u8>,
+}
+
+impl SecureCookies {
+    pub fn new(key: &[u8]) -> Self {
+        Self { key: key.to_vec() }
+    }
+
+    /// Sets the cookie `name` to `value` followed by its signature. The
+    /// signature covers the name too, so a signed value cannot be replayed
+    /// under another cookie's name. `value` must be a valid cookie value.
+    pub fn set_signed_cookie<'a>(
+        &self,
+        response: &'a mut HttpResponse,
+        name: &str,
+        value: &str,
+    ) -> &'a mut HttpResponse {
+        let signature = self.sign(name, value);
+        response.add_header(
+            "Set-Cookie",
+            &format!("{name}={value}.{signature}; Path=/; HttpOnly"),
+        )
+    }
+
+    /// The value of the cookie `name` if its signature is valid, or `None`
+    /// if it is missing or has been tampered with.
+    pub fn get_signed_cookie<'a>(&self, request: &'a HttpRequest, name: &str) -> Option<&'a str> {
+        let (value, signature) = request.get_cookie(name)?.rsplit_once('.')?;
+        constant_time_eq(signature.as_bytes(), self.sign(name, value).as_bytes()).then_some(value)
+    }
+
+    fn sign(&self, name: &str, value: &str) -> String {
+        let mac = hmac_sha256(&self.key, format!("{name}={value}").as_bytes());
+        mac.iter().fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+    }
+}
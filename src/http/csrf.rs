@@ -0,0 +1,126 @@
+use std::io;
+
+use super::{
+    errors::HttpError,
+    request::{HttpRequest, HttpRequestMethod},
+    session::{random_token, Session},
+    util::constant_time_eq,
+};
+
+/// The session key the token is kept under.
+const SESSION_KEY: &str = "csrf_token";
+
+/// The header a script can send the token in.
+pub const HEADER: &str = "X-CSRF-Token";
+
+/// The form field a page can send the token in.
+pub const FORM_FIELD: &str = "csrf_token";
+
+/// The session's CSRF token, created on first use, for a page to embed in
+/// its forms as the [`FORM_FIELD`] field or for a script to send in the
+/// [`HEADER`] header. Fails if a new token cannot be generated.
+pub fn token(session: &Session) -> io::Result<String> {
+    if let Some(token) = session.get(SESSION_KEY) {
+        return Ok(token);
+    }
+    let token = random_token()?;
+    session.insert(SESSION_KEY, &token);
+    Ok(token)
+}
+
+/// Checks that a request which can change state carries the session's
+/// CSRF token, in the [`HEADER`] header or the [`FORM_FIELD`] field of a
+/// form body. `GET`, `HEAD` and `OPTIONS` requests always pass. Fails
+/// with [`HttpError::CsrfTokenMismatch`], answered with `403`, if the
+/// token is missing or wrong.
+///
+/// ```
+/// use std::{
+///     io::{Read, Write},
+///     net::TcpStream,
+/// };
+///
+/// use http_server_starter_rust::http::{
+///     client::HttpClient,
+///     config::ServerConfig,
+///     csrf,
+///     response::{HttpResponse, HttpStatus},
+///     server::Server,
+///     session::MemoryStore,
+/// };
+///
+/// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+/// server.sessions("sid", MemoryStore::default());
+/// server.get("/form", |context| {
+///     let version = context.request.get_http_version().to_owned();
+///     let Ok(token) = csrf::token(context.session.unwrap()) else {
+///         return HttpResponse::from_status(version, HttpStatus::InternalServerError);
+///     };
+///     let mut response = HttpResponse::new(version);
+///     response.write_text(&token);
+///     response
+/// });
+/// server.post("/form", |context| {
+///     let version = context.request.get_http_version().to_owned();
+///     match csrf::verify(context.request, context.session.unwrap()) {
+///         Ok(()) => HttpResponse::ok(version, "text/plain", "saved"),
+///         Err(e) => HttpResponse::from_error(version, &e),
+///     }
+/// });
+/// let addr = server.local_addr()?;
+/// std::thread::spawn(move || server.run());
+///
+/// let form = HttpClient::get(&format!("http://{addr}/form")).unwrap();
+/// let token = String::from_utf8(form.get_body().to_vec()).unwrap();
+/// let cookie = form.get_header("Set-Cookie").unwrap();
+/// let session_cookie = cookie.split(';').next().unwrap();
+///
+/// let post = |headers: &str, body: &str| -> std::io::Result<String> {
+///     let mut stream = TcpStream::connect(addr)?;
+///     write!(
+///         stream,
+///         "POST /form HTTP/1.1\r\nCookie: {session_cookie}\r\n{headers}\
+///          Content-Type: application/x-www-form-urlencoded\r\n\
+///          Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+///         body.len()
+///     )?;
+///     let mut received = String::new();
+///     stream.read_to_string(&mut received)?;
+///     Ok(received)
+/// };
+///
+/// assert!(post("", &format!("csrf_token={token}"))?.ends_with("\r\n\r\nsaved"));
+/// let header = format!("x-csrf-token: {token}\r\n");
+/// assert!(post(&header, "")?.ends_with("\r\n\r\nsaved"));
+/// let forged = post("", "csrf_token=0123456789abcdef0123456789abcdef")?;
+/// assert!(forged.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+/// assert!(post("", "")?.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn verify(request: &HttpRequest, session: &Session) -> Result<(), HttpError> {
+    if matches!(
+        request.get_method(),
+        HttpRequestMethod::Get | HttpRequestMethod::Head | HttpRequestMethod::Options
+    ) {
+        return Ok(());
+    }
+    let submitted = match request.get_header(HEADER) {
+        Some(token) => Some(token.to_string()),
+        None => submitted_field(request),
+    };
+    match (submitted, session.get(SESSION_KEY)) {
+        (Some(submitted), Some(expected))
+            if constant_time_eq(submitted.as_bytes(), expected.as_bytes()) =>
+        {
+            Ok(())
+        }
+        _ => Err(HttpError::CsrfTokenMismatch),
+    }
+}
+
+fn submitted_field(request: &HttpRequest) -> Option<String> {
+    let form: Vec<(String, String)> = request.parse_body().ok()?;
+    form.into_iter()
+        .find(|(name, _)| name == FORM_FIELD)
+        .map(|(_, value)| value)
+}
@@ -0,0 +1,123 @@
+use super::{errors::HttpError, query::parse_query, request::HttpRequest};
+
+/// A type that can be built from a request body, for use with
+/// [`HttpRequest::parse_body`]. Implementations typically check the
+/// request's [`media_type`](HttpRequest::media_type) and refuse others
+/// with [`HttpError::UnsupportedMediaType`], which the server answers with
+/// `415`.
+///
+/// Custom types usually build on one of the provided implementations:
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use http_server_starter_rust::http::{
+///     config::ServerConfig, deserialize::FromRequestBody, errors::HttpError,
+///     request::HttpRequest,
+/// };
+///
+/// struct Login {
+///     user: String,
+///     remember: bool,
+/// }
+///
+/// impl FromRequestBody for Login {
+///     fn from_body(request: &HttpRequest) -> Result<Self, HttpError> {
+///         let form: Vec<(String, String)> = request.parse_body()?;
+///         let field = |name: &str| form.iter().find(|(key, _)| key == name).map(|(_, value)| value);
+///         Ok(Login {
+///             user: field("user")
+///                 .ok_or_else(|| HttpError::InvalidBody("missing user".to_string()))?
+///                 .clone(),
+///             remember: field("remember").is_some_and(|value| value == "on"),
+///         })
+///     }
+/// }
+///
+/// let parse = |content_type: &str, body: &str| -> Result<Login, HttpError> {
+///     let raw = format!(
+///         "POST /login HTTP/1.1\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n{body}",
+///         body.len()
+///     );
+///     let mut stream = Cursor::new(raw);
+///     let mut request = HttpRequest::from_reader(&mut stream, &ServerConfig::default())?;
+///     request.read_body(&mut stream)?;
+///     request.parse_body()
+/// };
+///
+/// let login = parse("application/x-www-form-urlencoded", "user=ada+l&remember=on")?;
+/// assert_eq!(login.user, "ada l");
+/// assert!(login.remember);
+/// assert!(matches!(
+///     parse("application/x-www-form-urlencoded", "remember=on"),
+///     Err(HttpError::InvalidBody(_))
+/// ));
+/// assert!(matches!(
+///     parse("application/json", "{}"),
+///     Err(HttpError::UnsupportedMediaType(_))
+/// ));
+/// # Ok::<(), HttpError>(())
+/// ```
+pub trait FromRequestBody: Sized {
+    fn from_body(request: &HttpRequest) -> Result<Self, HttpError>;
+}
+
+/// The raw body, empty if there is none.
+impl FromRequestBody for Vec<u8> {
+    fn from_body(request: &HttpRequest) -> Result<Self, HttpError> {
+        Ok(request.get_body().unwrap_or_default().to_vec())
+    }
+}
+
+/// The body as UTF-8 text, whatever its media type.
+impl FromRequestBody for String {
+    fn from_body(request: &HttpRequest) -> Result<Self, HttpError> {
+        Ok(String::from_utf8(Vec::from_body(request)?)?)
+    }
+}
+
+/// The fields of an `application/x-www-form-urlencoded` body, in order and
+/// with repeated names kept; see [`parse_query`].
+impl FromRequestBody for Vec<(String, String)> {
+    fn from_body(request: &HttpRequest) -> Result<Self, HttpError> {
+        request.require_media_type("application/x-www-form-urlencoded")?;
+        Ok(parse_query(&String::from_body(request)?))
+    }
+}
+
+/// The text of an `application/json` body, for a JSON parser to take
+/// from there.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use http_server_starter_rust::http::{
+///     config::ServerConfig, deserialize::JsonBody, errors::HttpError, request::HttpRequest,
+/// };
+///
+/// let parse = |content_type: &str, body: &str| -> Result<JsonBody, HttpError> {
+///     let raw = format!(
+///         "POST / HTTP/1.1\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n{body}",
+///         body.len()
+///     );
+///     let mut stream = Cursor::new(raw);
+///     let mut request = HttpRequest::from_reader(&mut stream, &ServerConfig::default())?;
+///     request.read_body(&mut stream)?;
+///     request.parse_body()
+/// };
+///
+/// assert_eq!(parse("application/json; charset=utf-8", "[1]")?.0, "[1]");
+/// let err = parse("text/plain", "[1]").err().unwrap();
+/// assert!(matches!(err, HttpError::UnsupportedMediaType(_)));
+/// assert_eq!(err.status().code(), 415);
+/// # Ok::<(), HttpError>(())
+/// ```
+#[derive(Debug)]
+pub struct JsonBody(pub String);
+
+impl FromRequestBody for JsonBody {
+    fn from_body(request: &HttpRequest) -> Result<Self, HttpError> {
+        request.require_media_type("application/json")?;
+        Ok(JsonBody(String::from_body(request)?))
+    }
+}
@@ -1,14 +1,184 @@
+use std::{io, string::FromUtf8Error};
+
+use super::response::HttpStatus;
+
+/// An error encountered while parsing or exchanging an HTTP message.
+///
+/// Errors wrapping a lower-level failure expose it through
+/// [`source`](std::error::Error::source):
+///
+/// ```
+/// use std::error::Error;
+/// use std::io::{self, ErrorKind};
+///
+/// use http_server_starter_rust::http::errors::HttpError;
+///
+/// let err = HttpError::from(io::Error::from(ErrorKind::UnexpectedEof));
+/// assert!(err.source().is_some());
+/// ```
+///
+/// Errors about a header give its line number in the head, counting the
+/// request line as line 1:
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+///
+/// let raw = "GET / HTTP/1.1\r\nHost: a\r\nNo colon here\r\n\r\n";
+/// let err = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())
+///     .err()
+///     .unwrap();
+/// assert_eq!(err.to_string(), "Malformed header on line 3: \"No colon here\"");
+/// ```
 #[derive(std::fmt::Debug)]
 pub enum HttpError {
     UnknownMethodError(String),
     UnknownHttpVersion(String),
+    UnsupportedHttpVersion(u8, u8),
+    BareLineFeed(String),
+    MalformedRequestLine(String),
+    MalformedHeader { line: usize, header: String },
+    FoldedHeader { line: usize, header: String },
+    InvalidContentLength(String),
+    ConflictingBodyLength,
+    InvalidTransferEncoding(String),
+    UnsupportedTransferEncoding(String),
+    ConflictingContentLength(String, String),
+    UnexpectedTrailingData,
+    MalformedStatusLine(String),
+    InvalidUrl(String),
+    PathEscapesRoot(String),
+    UnsupportedMediaType(String),
+    UnsupportedCharset(String),
+    InvalidBody(String),
+    CsrfTokenMismatch,
+    RequestHeadTooLarge,
+    TooManyHeaders,
+    Io(io::Error),
+    Utf8(FromUtf8Error),
 }
 impl std::fmt::Display for HttpError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::UnknownMethodError(method) => write!(f, "Unknown HTTP method: {}", method),
             Self::UnknownHttpVersion(version) => write!(f, "Unknown HTTP version: {}", version),
+            Self::UnsupportedHttpVersion(major, minor) => {
+                write!(f, "Unsupported HTTP version: HTTP/{}.{}", major, minor)
+            }
+            Self::BareLineFeed(line) => {
+                write!(
+                    f,
+                    "Line terminated by a bare LF instead of CRLF: {:?}",
+                    line
+                )
+            }
+            Self::MalformedRequestLine(line) => write!(f, "Malformed request line: {:?}", line),
+            Self::MalformedHeader { line, header } => {
+                write!(f, "Malformed header on line {}: {:?}", line, header)
+            }
+            Self::FoldedHeader { line, header } => {
+                write!(f, "Folded header line on line {}: {:?}", line, header)
+            }
+            Self::InvalidContentLength(length) => write!(f, "Invalid Content-Length: {:?}", length),
+            Self::ConflictingBodyLength => {
+                write!(f, "Both Content-Length and Transfer-Encoding are present")
+            }
+            Self::ConflictingContentLength(first, second) => {
+                write!(
+                    f,
+                    "Conflicting Content-Length values: {:?} and {:?}",
+                    first, second
+                )
+            }
+            Self::UnexpectedTrailingData => {
+                write!(f, "Unexpected data after a request without a body")
+            }
+            Self::InvalidTransferEncoding(encoding) => {
+                write!(f, "Transfer-Encoding must end with chunked: {:?}", encoding)
+            }
+            Self::UnsupportedTransferEncoding(encoding) => {
+                write!(f, "Unsupported Transfer-Encoding: {:?}", encoding)
+            }
+            Self::MalformedStatusLine(line) => write!(f, "Malformed status line: {:?}", line),
+            Self::InvalidUrl(url) => write!(f, "Invalid URL: {}", url),
+            Self::PathEscapesRoot(path) => write!(f, "Path escapes the root: {:?}", path),
+            Self::UnsupportedMediaType(media_type) => {
+                write!(f, "Unsupported Content-Type: {:?}", media_type)
+            }
+            Self::UnsupportedCharset(charset) => write!(f, "Unsupported charset: {:?}", charset),
+            Self::InvalidBody(reason) => write!(f, "Invalid request body: {}", reason),
+            Self::CsrfTokenMismatch => write!(f, "Missing or invalid CSRF token"),
+            Self::RequestHeadTooLarge => write!(f, "Request line and headers are too large"),
+            Self::TooManyHeaders => write!(f, "Too many headers"),
+            Self::Io(err) => write!(f, "I/O error: {}", err),
+            Self::Utf8(err) => write!(f, "Invalid UTF-8: {}", err),
+        }
+    }
+}
+impl std::error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Utf8(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for HttpError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<HttpError> for io::Error {
+    fn from(err: HttpError) -> Self {
+        match err {
+            HttpError::Io(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, err.to_string()),
+        }
+    }
+}
+
+impl From<FromUtf8Error> for HttpError {
+    fn from(err: FromUtf8Error) -> Self {
+        Self::Utf8(err)
+    }
+}
+
+impl HttpError {
+    /// The status a server should answer with when a request fails to parse
+    /// with this error.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let status = |version: &str| {
+    ///     let raw = format!("GET / {version}\r\n\r\n");
+    ///     let request = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default());
+    ///     request.err().map(|e| e.status().code())
+    /// };
+    ///
+    /// assert_eq!(status("HTTP/1.1"), None);
+    /// assert_eq!(status("HTTP/3.0"), Some(505));
+    /// assert_eq!(status("HTTP/1.2"), Some(505));
+    /// assert_eq!(status("FTP/1.0"), Some(400));
+    /// ```
+    pub fn status(&self) -> HttpStatus {
+        match self {
+            Self::RequestHeadTooLarge | Self::TooManyHeaders => {
+                HttpStatus::RequestHeaderFieldsTooLarge
+            }
+            Self::UnsupportedTransferEncoding(_) => HttpStatus::NotImplemented,
+            Self::UnsupportedHttpVersion(..) => HttpStatus::HttpVersionNotSupported,
+            Self::UnsupportedMediaType(_) | Self::UnsupportedCharset(_) => {
+                HttpStatus::UnsupportedMediaType
+            }
+            Self::CsrfTokenMismatch => HttpStatus::Forbidden,
+            _ => HttpStatus::BadRequest,
         }
     }
 }
-impl std::error::Error for HttpError {}
@@ -0,0 +1,163 @@
+use std::{
+    fs::{File, Metadata},
+    io::{self, ErrorKind},
+    path::{Component, Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use super::{
+    date::format_http_date,
+    range::ranged_file_response,
+    request::{HttpRequest, HttpRequestMethod},
+    response::{HttpResponse, HttpStatus},
+};
+
+const CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Serves files from a directory, honouring `Range` requests.
+#[derive(Clone)]
+pub struct FileHandler {
+    root: PathBuf,
+}
+
+impl FileHandler {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Responds with the file at `relative_path` below the root directory.
+    /// Paths with `..` or empty segments, or that are absolute, are refused
+    /// with `403` so that requests cannot escape the root.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, files::FileHandler, request::HttpRequest,
+    /// };
+    ///
+    /// let outside = std::env::temp_dir().join("file-handler-escape-doctest.txt");
+    /// std::fs::write(&outside, "secret")?;
+    /// let root = std::env::temp_dir().join("file-handler-escape-doctest");
+    /// std::fs::create_dir_all(&root)?;
+    /// let files = FileHandler::new(&root);
+    ///
+    /// // `GET /files//tmp/x` gives a `/files/*` route the wildcard `/tmp/x`.
+    /// let wildcard = outside.to_str().unwrap();
+    /// let raw = format!("GET /files/{wildcard} HTTP/1.1\r\n\r\n");
+    /// let request = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())?;
+    /// for path in [wildcard, "a//b", "../file-handler-escape-doctest.txt"] {
+    ///     assert_eq!(files.serve(&request, path).get_status_code(), 403, "{path}");
+    /// }
+    /// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+    /// ```
+    ///
+    /// A `HEAD` request gets the same headers as a `GET` from the file's
+    /// metadata alone, without the file being opened:
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, files::FileHandler, request::HttpRequest,
+    /// };
+    ///
+    /// let root = std::env::temp_dir().join("file-handler-head-doctest");
+    /// std::fs::create_dir_all(&root)?;
+    /// std::fs::write(root.join("hello.txt"), "hello")?;
+    /// let files = FileHandler::new(&root);
+    ///
+    /// let respond = |method: &str| {
+    ///     let raw = format!("{method} /hello.txt HTTP/1.1\r\n\r\n");
+    ///     let request =
+    ///         HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default()).unwrap();
+    ///     files.serve(&request, "hello.txt")
+    /// };
+    /// let (get, head) = (respond("GET"), respond("HEAD"));
+    ///
+    /// // A streamed `GET` body is not part of `to_bytes`, so only the heads
+    /// // are compared.
+    /// assert_eq!(get.to_bytes(), head.to_bytes());
+    /// assert_eq!(head.get_header("Content-Length"), Some("5"));
+    /// assert!(head.get_header("ETag").is_some());
+    /// assert!(head.get_header("Last-Modified").is_some());
+    /// assert!(!head.has_body());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn serve(&self, request: &HttpRequest, relative_path: &str) -> HttpResponse {
+        let mut response = HttpResponse::new(request.get_http_version().to_owned());
+        let Some(path) = self.resolve(relative_path) else {
+            response.set_status(HttpStatus::Forbidden);
+            return response;
+        };
+        let metadata = match path.metadata() {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => {
+                response.set_status(HttpStatus::NotFound);
+                return response;
+            }
+            Err(e) => return failed(response, relative_path, e),
+        };
+
+        if *request.get_method() == HttpRequestMethod::Head {
+            response
+                .add_header("Accept-Ranges", "bytes")
+                .add_header("Content-Type", CONTENT_TYPE);
+            add_validators(&mut response, &metadata)
+                .add_header("Content-Length", &metadata.len().to_string());
+            return response;
+        }
+
+        match File::open(path).and_then(|file| ranged_file_response(request, file, CONTENT_TYPE)) {
+            Ok(mut response) => {
+                add_validators(&mut response, &metadata);
+                response
+            }
+            Err(e) => failed(response, relative_path, e),
+        }
+    }
+
+    /// The path of `relative_path` below the root, or `None` if it could
+    /// point outside it. Joining an absolute path, as an empty segment
+    /// would make it, replaces the root rather than extending it, so the
+    /// path is built one normal component at a time.
+    fn resolve(&self, relative_path: &str) -> Option<PathBuf> {
+        if relative_path.split('/').any(str::is_empty) {
+            return None;
+        }
+        let mut path = self.root.clone();
+        for component in Path::new(relative_path).components() {
+            match component {
+                Component::Normal(part) => path.push(part),
+                _ => return None,
+            }
+        }
+        Some(path)
+    }
+}
+
+/// Adds the `Last-Modified` and `ETag` headers for a file. The entity tag
+/// is made of the file's size and modification time.
+fn add_validators<'a>(response: &'a mut HttpResponse, metadata: &Metadata) -> &'a mut HttpResponse {
+    let Ok(modified) = metadata.modified() else {
+        return response;
+    };
+    let nanos = modified
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    response
+        .add_header("Last-Modified", &format_http_date(modified))
+        .add_header("ETag", &format!("\"{:x}-{:x}\"", metadata.len(), nanos))
+}
+
+fn failed(mut response: HttpResponse, relative_path: &str, e: io::Error) -> HttpResponse {
+    if e.kind() == ErrorKind::NotFound {
+        response.set_status(HttpStatus::NotFound);
+    } else {
+        println!("error opening {}: {}", relative_path, e);
+        response.set_status(HttpStatus::InternalServerError);
+    }
+    response
+}
@@ -0,0 +1,34 @@
+use std::fmt::Write;
+
+/// Encodes `value` as a JSON string literal, quotes included.
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Encodes string pairs as a JSON object, keeping their order.
+pub fn object<'a, I>(members: I) -> String
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    let members: Vec<String> = members
+        .into_iter()
+        .map(|(key, value)| format!("{}:{}", quote(key), quote(value)))
+        .collect();
+    format!("{{{}}}", members.join(","))
+}
@@ -0,0 +1,110 @@
+use std::{
+    io::{self, Read, Write},
+    net::{self, SocketAddr, TcpListener, TcpStream},
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::{
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+};
+
+/// The socket a server accepts connections on.
+pub(crate) enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener, PathBuf),
+}
+
+impl Listener {
+    pub(crate) fn local_addr(&self) -> io::Result<SocketAddr> {
+        match self {
+            Self::Tcp(listener) => listener.local_addr(),
+            #[cfg(unix)]
+            Self::Unix(..) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "a Unix domain socket has no IP address",
+            )),
+        }
+    }
+
+    /// Connects to the listener, so that a blocked accept returns.
+    pub(crate) fn waker(&self) -> io::Result<Box<dyn Fn() + Send + Sync>> {
+        match self {
+            Self::Tcp(listener) => {
+                let addr = listener.local_addr()?;
+                Ok(Box::new(move || {
+                    let _ = TcpStream::connect(addr);
+                }))
+            }
+            #[cfg(unix)]
+            Self::Unix(_, path) => {
+                let path = path.clone();
+                Ok(Box::new(move || {
+                    let _ = UnixStream::connect(&path);
+                }))
+            }
+        }
+    }
+}
+
+/// A connection accepted by a [`Listener`].
+pub(crate) trait Accepted: Read + Write + Send + Sized + 'static {
+    /// The client's address, which only TCP connections have.
+    fn peer_addr(&self) -> Option<SocketAddr>;
+
+    fn set_read_timeout(&self, timeout: Duration) -> io::Result<()>;
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()>;
+
+    fn try_clone(&self) -> io::Result<Self>;
+
+    /// Closes both directions, failing reads and writes on every clone.
+    fn shutdown(&self);
+}
+
+impl Accepted for TcpStream {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        TcpStream::peer_addr(self).ok()
+    }
+
+    fn set_read_timeout(&self, timeout: Duration) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, Some(timeout))
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn try_clone(&self) -> io::Result<Self> {
+        TcpStream::try_clone(self)
+    }
+
+    fn shutdown(&self) {
+        let _ = TcpStream::shutdown(self, net::Shutdown::Both);
+    }
+}
+
+#[cfg(unix)]
+impl Accepted for UnixStream {
+    fn peer_addr(&self) -> Option<SocketAddr> {
+        None
+    }
+
+    fn set_read_timeout(&self, timeout: Duration) -> io::Result<()> {
+        UnixStream::set_read_timeout(self, Some(timeout))
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn try_clone(&self) -> io::Result<Self> {
+        UnixStream::try_clone(self)
+    }
+
+    fn shutdown(&self) {
+        let _ = UnixStream::shutdown(self, net::Shutdown::Both);
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt::Write;
+
+use super::stats::ServerStats;
+
+/// The media type of the Prometheus text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Renders `stats` in the Prometheus text exposition format, with a
+/// `# HELP` and `# TYPE` line before each metric family.
+///
+/// Requests relayed by the proxy have no status of their own, so they are
+/// counted in `http_requests_handled_total` but not in `http_requests_total`.
+///
+/// ```
+/// use http_server_starter_rust::http::{metrics, stats::ServerStats};
+///
+/// let stats = ServerStats {
+///     connections_accepted: 3,
+///     active_connections: 1,
+///     requests_handled: 3,
+///     responses_2xx: 2,
+///     responses_4xx: 1,
+///     responses_by_status: vec![(200, 2), (404, 1)],
+///     ..ServerStats::default()
+/// };
+/// let text = metrics::render(&stats);
+/// assert!(text.contains("http_requests_total{status=\"200\"} 2\n"));
+/// assert!(text.contains("http_requests_total{status=\"404\"} 1\n"));
+/// assert!(text.contains("# TYPE http_connections_active gauge\n"));
+///
+/// // Every sample is `name[{labels}] value` and belongs to a family that
+/// // was described and typed before it.
+/// let mut typed = Vec::new();
+/// for line in text.lines() {
+///     if let Some(comment) = line.strip_prefix("# ") {
+///         let mut words = comment.splitn(3, ' ');
+///         let (kind, name, rest) = (words.next(), words.next(), words.next());
+///         assert!(matches!(kind, Some("HELP" | "TYPE")) && rest.is_some(), "{line}");
+///         if kind == Some("TYPE") {
+///             assert!(matches!(rest, Some("counter" | "gauge")), "{line}");
+///             typed.push(name.unwrap().to_string());
+///         }
+///         continue;
+///     }
+///     let (series, value) = line.rsplit_once(' ').unwrap();
+///     value.parse::<f64>().unwrap();
+///     let name = match series.split_once('{') {
+///         Some((name, labels)) => {
+///             let labels = labels.strip_suffix('}').unwrap();
+///             for label in labels.split(',') {
+///                 let (key, value) = label.split_once('=').unwrap();
+///                 assert!(key.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_'));
+///                 assert!(value.starts_with('"') && value.ends_with('"'));
+///             }
+///             name
+///         }
+///         None => series,
+///     };
+///     assert!(name.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_'));
+///     assert_eq!(typed.last().map(String::as_str), Some(name), "{line}");
+/// }
+/// ```
+pub fn render(stats: &ServerStats) -> String {
+    let mut text = String::new();
+    family(
+        &mut text,
+        "http_connections_accepted_total",
+        "counter",
+        "Connections accepted since the server started.",
+    );
+    sample(
+        &mut text,
+        "http_connections_accepted_total",
+        &[],
+        stats.connections_accepted,
+    );
+    family(
+        &mut text,
+        "http_connections_active",
+        "gauge",
+        "Connections currently being served.",
+    );
+    sample(
+        &mut text,
+        "http_connections_active",
+        &[],
+        stats.active_connections,
+    );
+    family(
+        &mut text,
+        "http_requests_handled_total",
+        "counter",
+        "Requests handled, including proxied ones.",
+    );
+    sample(
+        &mut text,
+        "http_requests_handled_total",
+        &[],
+        stats.requests_handled,
+    );
+    family(
+        &mut text,
+        "http_requests_total",
+        "counter",
+        "Responses sent, by status code.",
+    );
+    for (status_code, count) in &stats.responses_by_status {
+        sample(
+            &mut text,
+            "http_requests_total",
+            &[("status", &status_code.to_string())],
+            *count,
+        );
+    }
+    text
+}
+
+/// Escapes a label value: backslashes, double quotes and line feeds are
+/// the only characters the format requires to be escaped.
+///
+/// ```
+/// use http_server_starter_rust::http::metrics::escape_label_value;
+///
+/// assert_eq!(escape_label_value("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+/// ```
+pub fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn family(text: &mut String, name: &str, kind: &str, help: &str) {
+    // Writing to a String cannot fail.
+    let _ = writeln!(text, "# HELP {name} {help}");
+    let _ = writeln!(text, "# TYPE {name} {kind}");
+}
+
+fn sample(text: &mut String, name: &str, labels: &[(&str, &str)], value: u64) {
+    text.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(key, value)| format!("{key}=\"{}\"", escape_label_value(value)))
+            .collect();
+        let _ = write!(text, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(text, " {value}");
+}
@@ -0,0 +1,67 @@
+/// Parses a comma-separated header of values with optional `q` weights,
+/// such as `Accept-Language: en-US, en;q=0.8`, into values and weights.
+/// Values without a weight get `1.0`; entries with a malformed weight are
+/// skipped.
+pub(crate) fn weighted_values(header: &str) -> Vec<(&str, f32)> {
+    header
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';').map(str::trim);
+            let value = parts.next().filter(|value| !value.is_empty())?;
+            let mut weight = 1.0;
+            for param in parts {
+                let Some((name, q)) = param.split_once('=') else {
+                    continue;
+                };
+                if name.trim().eq_ignore_ascii_case("q") {
+                    weight = q.trim().parse().ok().filter(|q| (0.0..=1.0).contains(q))?;
+                }
+            }
+            Some((value, weight))
+        })
+        .collect()
+}
+
+/// Picks the language from `available` that an `Accept-Language` header
+/// weights highest; see [`HttpRequest::preferred_language`].
+///
+/// [`HttpRequest::preferred_language`]: super::request::HttpRequest::preferred_language
+pub(crate) fn preferred_language<'a>(
+    header: Option<&str>,
+    available: &[&'a str],
+) -> Option<&'a str> {
+    let ranges = header.map_or_else(Vec::new, weighted_values);
+    if ranges.is_empty() {
+        return available.first().copied();
+    }
+
+    let mut best: Option<(&str, f32)> = None;
+    for &language in available {
+        let Some(weight) = language_weight(&ranges, language) else {
+            continue;
+        };
+        // A weight of zero never beats the starting point.
+        if weight > best.map_or(0.0, |(_, best)| best) {
+            best = Some((language, weight));
+        }
+    }
+    best.map(|(language, _)| language)
+}
+
+/// The weight of the most specific range matching `language`, where a
+/// range matches itself, any tag it is a prefix of (`en` matches
+/// `en-US`), and, as `*`, everything.
+fn language_weight(ranges: &[(&str, f32)], language: &str) -> Option<f32> {
+    ranges
+        .iter()
+        .filter(|(range, _)| {
+            *range == "*"
+                || language.eq_ignore_ascii_case(range)
+                || (language
+                    .get(..range.len())
+                    .is_some_and(|prefix| prefix.eq_ignore_ascii_case(range))
+                    && language.as_bytes().get(range.len()) == Some(&b'-'))
+        })
+        .max_by_key(|(range, _)| if *range == "*" { 0 } else { range.len() })
+        .map(|&(_, weight)| weight)
+}
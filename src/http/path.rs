@@ -0,0 +1,66 @@
+use super::query::percent_decode;
+
+/// Resolves the `.` and `..` segments of a URL path, returning `None` if a
+/// `..` would climb above the root. Segments are compared after
+/// percent-decoding, so `%2e%2e` counts as `..`.
+///
+/// A path ending in a `.` or `..` segment keeps its trailing `/`.
+///
+/// ```
+/// use http_server_starter_rust::http::path::canonicalize_path;
+///
+/// assert_eq!(canonicalize_path("/a/../b").as_deref(), Some("/b"));
+/// assert_eq!(canonicalize_path("/a/./b").as_deref(), Some("/a/b"));
+/// assert_eq!(canonicalize_path("/a/b/..").as_deref(), Some("/a/"));
+/// assert_eq!(canonicalize_path("/").as_deref(), Some("/"));
+/// assert_eq!(canonicalize_path("/../etc"), None);
+/// assert_eq!(canonicalize_path("/a/%2e%2e/%2E%2E/etc"), None);
+/// ```
+pub fn canonicalize_path(path: &str) -> Option<String> {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut parts = path.strip_prefix('/').unwrap_or(path).split('/').peekable();
+    while let Some(segment) = parts.next() {
+        let last = parts.peek().is_none();
+        match percent_decode(segment).as_str() {
+            "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            _ => {
+                segments.push(segment);
+                continue;
+            }
+        }
+        if last {
+            segments.push("");
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+/// Collapses each run of slashes in the path of `target` into one, leaving
+/// any query string as it is.
+///
+/// ```
+/// use http_server_starter_rust::http::path::merge_slashes;
+///
+/// assert_eq!(merge_slashes("/a///b//"), "/a/b/");
+/// assert_eq!(merge_slashes("//a?redirect=//b"), "/a?redirect=//b");
+/// ```
+pub fn merge_slashes(target: &str) -> String {
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (target, None),
+    };
+    let mut merged = String::with_capacity(target.len());
+    for c in path.chars() {
+        if !(c == '/' && merged.ends_with('/')) {
+            merged.push(c);
+        }
+    }
+    if let Some(query) = query {
+        merged.push('?');
+        merged.push_str(query);
+    }
+    merged
+}
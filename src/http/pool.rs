@@ -0,0 +1,172 @@
+use std::{
+    any::Any,
+    io,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// A fixed set of named worker threads that run jobs from a shared queue.
+///
+/// A job that panics is logged with the name of the worker that ran it, and
+/// the worker carries on with the next job.
+///
+/// ```
+/// use std::{sync::mpsc, thread};
+///
+/// use http_server_starter_rust::http::pool::ThreadPool;
+///
+/// let pool = ThreadPool::new(1, "worker")?;
+/// let (sender, receiver) = mpsc::channel();
+/// pool.execute(|| panic!("boom"));
+/// for job in 0..2 {
+///     let sender = sender.clone();
+///     pool.execute(move || {
+///         let name = thread::current().name().map(str::to_string);
+///         sender.send((job, name)).unwrap();
+///     });
+/// }
+///
+/// // The only worker survived the panic and ran both later jobs.
+/// let worker = Some("worker-0".to_string());
+/// assert_eq!(receiver.recv().unwrap(), (0, worker.clone()));
+/// assert_eq!(receiver.recv().unwrap(), (1, worker));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct ThreadPool {
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+    /// Jobs waiting for a worker, not counting those running.
+    queued: Arc<AtomicUsize>,
+    max_queued: Option<usize>,
+}
+
+impl ThreadPool {
+    /// Starts `size` workers named `{name_prefix}-0`, `{name_prefix}-1`, ...
+    pub fn new(size: usize, name_prefix: &str) -> io::Result<Self> {
+        Self::with_max_queued(size, name_prefix, None)
+    }
+
+    /// Like [`new`](Self::new), but [`is_full`](Self::is_full) once
+    /// `max_queued` jobs are waiting for a worker. `None` leaves the queue
+    /// unbounded.
+    ///
+    /// ```
+    /// use std::{sync::mpsc, thread, time::Duration};
+    ///
+    /// use http_server_starter_rust::http::pool::ThreadPool;
+    ///
+    /// let pool = ThreadPool::with_max_queued(1, "worker", Some(1))?;
+    /// let (release, blocked) = mpsc::channel::<()>();
+    /// let (started, running) = mpsc::channel();
+    /// pool.execute(move || {
+    ///     started.send(()).unwrap();
+    ///     blocked.recv().unwrap();
+    /// });
+    /// running.recv().unwrap();
+    /// assert!(!pool.is_full());
+    /// pool.execute(|| {});
+    /// assert!(pool.is_full());
+    ///
+    /// release.send(()).unwrap();
+    /// while pool.is_full() {
+    ///     thread::sleep(Duration::from_millis(5));
+    /// }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_max_queued(
+        size: usize,
+        name_prefix: &str,
+        max_queued: Option<usize>,
+    ) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let queued = Arc::new(AtomicUsize::new(0));
+
+        let mut workers = Vec::with_capacity(size);
+        for id in 0..size {
+            let receiver = Arc::clone(&receiver);
+            let queued = Arc::clone(&queued);
+            let name = format!("{name_prefix}-{id}");
+            let worker = thread::Builder::new()
+                .name(name.clone())
+                .spawn(move || loop {
+                    // The lock is released before the job runs.
+                    let job = match receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => return,
+                    };
+                    let Ok(job) = job else {
+                        return;
+                    };
+                    queued.fetch_sub(1, Ordering::Relaxed);
+                    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(job)) {
+                        println!("{}", panic_entry(&name, &*payload));
+                    }
+                })?;
+            workers.push(worker);
+        }
+
+        Ok(Self {
+            sender: Some(sender),
+            workers,
+            queued,
+            max_queued,
+        })
+    }
+
+    /// Queues `job` for the next free worker. This does not check
+    /// [`is_full`](Self::is_full); callers that want to turn work away
+    /// check it first.
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        if let Some(sender) = &self.sender {
+            self.queued.fetch_add(1, Ordering::Relaxed);
+            // Sending only fails once every worker has exited.
+            let _ = sender.send(Box::new(job));
+        }
+    }
+
+    /// Whether as many jobs as allowed are waiting for a worker. Only
+    /// running jobs finishing can make room, so a caller that is the only
+    /// one queueing jobs can rely on the answer until it queues another.
+    pub fn is_full(&self) -> bool {
+        self.max_queued
+            .is_some_and(|max_queued| self.queued.load(Ordering::Relaxed) >= max_queued)
+    }
+}
+
+impl Drop for ThreadPool {
+    /// Lets the workers finish the jobs already queued, then joins them.
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Formats the line a worker logs when a job panics.
+///
+/// ```
+/// use std::panic;
+///
+/// use http_server_starter_rust::http::pool::panic_entry;
+///
+/// let payload = panic::catch_unwind(|| panic!("no route for {}", "/a")).unwrap_err();
+/// assert_eq!(panic_entry("worker-3", &*payload), "worker-3 panicked: no route for /a");
+/// ```
+pub fn panic_entry(worker: &str, payload: &(dyn Any + Send)) -> String {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    };
+    format!("{} panicked: {}", worker, message)
+}
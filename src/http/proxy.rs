@@ -0,0 +1,635 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpStream},
+};
+
+use super::{
+    body::{read_chunk_end, read_chunk_size, read_trailers, BodyDecoder, ChunkLimits},
+    client::{split_url, with_default_port},
+    config::ServerConfig,
+    errors::HttpError,
+    request::{
+        check_body_framing, connection_options, find_header, parse_content_length, read_headers,
+        HttpRequest, HttpRequestLine, HttpRequestMethod,
+    },
+    response::{HttpResponse, HttpResponseStatusLine, HttpStatus},
+    HttpVersion,
+};
+
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "Connection",
+    "Keep-Alive",
+    "Proxy-Authenticate",
+    "Proxy-Authorization",
+    "TE",
+    "Trailers",
+    "Transfer-Encoding",
+    "Upgrade",
+];
+
+/// An upstream response whose head has been read but whose body has not.
+struct UpstreamResponse {
+    reader: BufReader<TcpStream>,
+    status_line: HttpResponseStatusLine,
+    headers: Vec<(String, String)>,
+}
+
+/// How an upstream response delimits its body.
+enum Framing {
+    Length(u64),
+    Chunked,
+    /// The body ends when the upstream closes the connection.
+    Close,
+}
+
+/// Where a proxy sends requests.
+enum Upstream {
+    /// A single server, addressed as `host:port`.
+    Fixed(String),
+    /// The server named by each request's absolute-form target.
+    FromTarget,
+}
+
+/// The connection details for one forwarded request.
+struct Destination<'a> {
+    address: String,
+    host: &'a str,
+    path: &'a str,
+}
+
+/// Relays requests to a single upstream server or, set up with
+/// [`forwarding`](Self::forwarding), to whichever server each request
+/// names.
+///
+/// The request and response bodies are streamed rather than buffered, and
+/// hop-by-hop headers are not forwarded in either direction. A chunked
+/// response is relayed chunked, except to HTTP/1.0 clients, which get the
+/// body decoded and ended by the connection closing.
+///
+/// ```
+/// use std::{
+///     io::{Read, Write},
+///     net::TcpStream,
+///     thread,
+/// };
+///
+/// use http_server_starter_rust::http::{
+///     config::ServerConfig, proxy::ReverseProxy, response::HttpResponse, server::Server,
+/// };
+///
+/// let mut upstream = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+/// upstream.get("/feed", |context| {
+///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+///     response.stream_chunked(|writer| {
+///         writer.send_chunk(b"first ")?;
+///         writer.send_chunk(b"second")
+///     });
+///     response
+/// });
+/// let upstream_addr = upstream.local_addr()?;
+/// thread::spawn(move || upstream.run());
+///
+/// let mut proxy = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+/// proxy.set_proxy(ReverseProxy::new(&upstream_addr.to_string()));
+/// let proxy_addr = proxy.local_addr()?;
+/// thread::spawn(move || proxy.run());
+///
+/// let get = |version: &str| -> std::io::Result<String> {
+///     let mut stream = TcpStream::connect(proxy_addr)?;
+///     write!(stream, "GET /feed {version}\r\n\r\n")?;
+///     let mut received = String::new();
+///     stream.read_to_string(&mut received)?;
+///     Ok(received)
+/// };
+/// let chunked = get("HTTP/1.1")?;
+/// assert!(chunked.contains("\r\nTransfer-Encoding: chunked\r\n"));
+/// assert!(chunked.ends_with("\r\n\r\n6\r\nfirst \r\n6\r\nsecond\r\n0\r\n\r\n"));
+///
+/// let old = get("HTTP/1.0")?;
+/// assert!(old.starts_with("HTTP/1.0 200 OK\r\n"));
+/// assert!(!old.contains("Transfer-Encoding"));
+/// assert!(old.ends_with("\r\nConnection: close\r\n\r\nfirst second"));
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct ReverseProxy {
+    upstream: Upstream,
+}
+
+impl ReverseProxy {
+    /// A proxy relaying every request to the server at `upstream`, given as
+    /// `host:port`.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::{TcpListener, TcpStream},
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, proxy::ReverseProxy, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// // Echoes the names of the headers it received, then the body.
+    /// let mut upstream = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// upstream.post("/echo", |context| {
+    ///     let request = context.request;
+    ///     let headers = request.get_headers().iter();
+    ///     let names: Vec<&str> = headers.map(|(name, _)| name.as_str()).collect();
+    ///     let body = String::from_utf8_lossy(request.get_body().unwrap_or_default());
+    ///     let mut response = HttpResponse::new(request.get_http_version().to_owned());
+    ///     response.write_text(&format!("{}\n{}", names.join(","), body));
+    ///     response
+    /// });
+    /// let upstream_addr = upstream.local_addr()?;
+    /// thread::spawn(move || upstream.run());
+    ///
+    /// let proxy_to = |upstream: &str| -> std::io::Result<_> {
+    ///     let mut proxy = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    ///     proxy.set_proxy(ReverseProxy::new(upstream));
+    ///     let proxy_addr = proxy.local_addr()?;
+    ///     thread::spawn(move || proxy.run());
+    ///     Ok(proxy_addr)
+    /// };
+    /// let send = |proxy_addr, raw: &str| -> std::io::Result<String> {
+    ///     let mut stream = TcpStream::connect(proxy_addr)?;
+    ///     stream.write_all(raw.as_bytes())?;
+    ///     let mut received = String::new();
+    ///     stream.read_to_string(&mut received)?;
+    ///     Ok(received)
+    /// };
+    ///
+    /// let proxy_addr = proxy_to(&upstream_addr.to_string())?;
+    /// let echoed = send(
+    ///     proxy_addr,
+    ///     "POST /echo HTTP/1.1\r\nConnection: keep-alive, X-Hop\r\nKeep-Alive: timeout=5\r\n\
+    ///      X-Hop: 1\r\nX-End: 2\r\nContent-Length: 5\r\n\r\nhello",
+    /// )?;
+    /// let (_, body) = echoed.split_once("\r\n\r\n").unwrap();
+    /// let (names, body) = body.split_once('\n').unwrap();
+    /// let names: Vec<&str> = names.split(',').collect();
+    /// assert!(names.contains(&"X-End") && names.contains(&"Content-Length"));
+    /// assert!(!names.contains(&"Keep-Alive") && !names.contains(&"X-Hop"));
+    /// // The proxy asks the upstream to close instead of relaying the client's choice.
+    /// assert_eq!(names.iter().filter(|name| **name == "Connection").count(), 1);
+    /// assert_eq!(body, "hello");
+    ///
+    /// // Nothing listens on a port once its listener is dropped.
+    /// let unused = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+    /// let proxy_addr = proxy_to(&unused.to_string())?;
+    /// let unreachable = send(proxy_addr, "GET / HTTP/1.1\r\n\r\n")?;
+    /// assert!(unreachable.starts_with("HTTP/1.1 502 Bad Gateway\r\n"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn new(upstream: &str) -> Self {
+        Self {
+            upstream: Upstream::Fixed(upstream.to_string()),
+        }
+    }
+
+    /// A forward proxy, which relays each request to the host given in its
+    /// absolute-form target, as in `GET http://example.com/path HTTP/1.1`,
+    /// instead of to a fixed upstream. The target's authority replaces any
+    /// `Host` header, and requests without an `http://` target are answered
+    /// with `400 Bad Request`.
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpStream;
+    /// use std::thread;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, proxy::ReverseProxy, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut upstream = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// upstream.get("/hello", |context| {
+    ///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///     response.write_text(&format!("hello from {}", context.request.get_host().unwrap_or("?")));
+    ///     response
+    /// });
+    /// let upstream_addr = upstream.local_addr()?;
+    /// thread::spawn(move || upstream.run());
+    ///
+    /// let mut proxy = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// proxy.set_proxy(ReverseProxy::forwarding());
+    /// let proxy_addr = proxy.local_addr()?;
+    /// thread::spawn(move || proxy.run());
+    ///
+    /// let mut stream = TcpStream::connect(proxy_addr)?;
+    /// write!(
+    ///     stream,
+    ///     "GET http://{upstream_addr}/hello HTTP/1.1\r\nHost: elsewhere\r\n\r\n"
+    /// )?;
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response)?;
+    ///
+    /// assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// assert!(response.ends_with(&format!("\r\n\r\nhello from {upstream_addr}")));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn forwarding() -> Self {
+        Self {
+            upstream: Upstream::FromTarget,
+        }
+    }
+
+    /// Forwards `request` upstream and writes the upstream response to
+    /// `client`. The request body, if any, is read from `client` as well, so
+    /// it must be the connection the request head was parsed from.
+    ///
+    /// A request whose body framing is invalid or ambiguous, such as a
+    /// malformed `Content-Length` or one sent with `Transfer-Encoding`, is
+    /// answered with `400 Bad Request` rather than forwarded, since the
+    /// upstream might delimit it differently. If the upstream cannot be
+    /// reached or sends a malformed or ambiguously framed response head, a
+    /// `502 Bad Gateway` is written instead. `config` bounds the upstream
+    /// response head and the lines of chunked bodies in both directions.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::{TcpListener, TcpStream},
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, proxy::ReverseProxy, server::Server,
+    /// };
+    ///
+    /// // An upstream that frames its responses both by length and by chunks.
+    /// let upstream = TcpListener::bind("127.0.0.1:0")?;
+    /// let upstream_addr = upstream.local_addr()?;
+    /// thread::spawn(move || {
+    ///     for mut stream in upstream.incoming().map_while(Result::ok) {
+    ///         let _ = stream.read(&mut [0; 1024]);
+    ///         let _ = stream.write_all(
+    ///             b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\
+    ///               Transfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+    ///         );
+    ///     }
+    /// });
+    ///
+    /// let mut proxy = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// proxy.set_proxy(ReverseProxy::new(&upstream_addr.to_string()));
+    /// let proxy_addr = proxy.local_addr()?;
+    /// thread::spawn(move || proxy.run());
+    ///
+    /// let send = |raw: &str| -> std::io::Result<String> {
+    ///     let mut stream = TcpStream::connect(proxy_addr)?;
+    ///     stream.write_all(raw.as_bytes())?;
+    ///     let mut received = String::new();
+    ///     stream.read_to_string(&mut received)?;
+    ///     Ok(received)
+    /// };
+    /// let signed = send("POST / HTTP/1.1\r\nContent-Length: +5\r\n\r\nhello")?;
+    /// assert!(signed.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    /// let ambiguous = send("GET / HTTP/1.1\r\n\r\n")?;
+    /// assert!(ambiguous.starts_with("HTTP/1.1 502 Bad Gateway\r\n"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// Interim `1xx` responses from the upstream are skipped, and only the
+    /// final response is relayed. The client connection is closed after it:
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::{TcpListener, TcpStream},
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, proxy::ReverseProxy, server::Server,
+    /// };
+    ///
+    /// let upstream = TcpListener::bind("127.0.0.1:0")?;
+    /// let upstream_addr = upstream.local_addr()?;
+    /// thread::spawn(move || {
+    ///     for mut stream in upstream.incoming().map_while(Result::ok) {
+    ///         let _ = stream.read(&mut [0; 1024]);
+    ///         let _ = stream.write_all(
+    ///             b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 103 Early Hints\r\n\
+    ///               Link: </a.css>; rel=preload\r\n\r\n\
+    ///               HTTP/1.1 200 OK\r\nContent-Length: 2\r\nKeep-Alive: timeout=5\r\n\r\nok",
+    ///         );
+    ///     }
+    /// });
+    ///
+    /// let mut proxy = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// proxy.set_proxy(ReverseProxy::new(&upstream_addr.to_string()));
+    /// let proxy_addr = proxy.local_addr()?;
+    /// thread::spawn(move || proxy.run());
+    ///
+    /// let mut stream = TcpStream::connect(proxy_addr)?;
+    /// stream.write_all(b"GET / HTTP/1.1\r\n\r\n")?;
+    /// let mut received = String::new();
+    /// stream.read_to_string(&mut received)?;
+    /// assert!(received.starts_with("HTTP/1.1 200 OK\r\n"), "{received:?}");
+    /// assert!(!received.contains("Keep-Alive") && !received.contains("Link"));
+    /// assert!(received.contains("\r\nConnection: close\r\n"));
+    /// assert!(received.ends_with("\r\n\r\nok"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn forward<C: BufRead + Write>(
+        &self,
+        request: &HttpRequest,
+        client: &mut C,
+        peer_addr: Option<SocketAddr>,
+        config: &ServerConfig,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let limits = ChunkLimits::new(config);
+        let bad_request = |client: &mut C, e: &dyn std::error::Error| {
+            println!("proxy error: {}", e);
+            let mut response = HttpResponse::new(request.get_http_version().to_owned());
+            response
+                .set_status(HttpStatus::BadRequest)
+                .add_header("Connection", "close");
+            response.write_to(client)
+        };
+        let bad_gateway = |client: &mut C, e: &dyn std::error::Error| {
+            println!("upstream error: {}", e);
+            let mut response = HttpResponse::new(request.get_http_version().to_owned());
+            response
+                .set_status(HttpStatus::BadGateway)
+                .add_header("Connection", "close");
+            response.write_to(client)
+        };
+
+        let body = match BodyDecoder::for_headers(request.get_headers(), limits) {
+            Ok(body) => body,
+            Err(e) => return Ok(bad_request(client, &e)?),
+        };
+        let destination = match self.destination(request) {
+            Ok(destination) => destination,
+            Err(e) => return Ok(bad_request(client, &e)?),
+        };
+        let UpstreamResponse {
+            reader: mut upstream,
+            status_line,
+            mut headers,
+        } = match self.open_upstream(request, body, &destination, client, peer_addr, config) {
+            Ok(response) => response,
+            Err(e) => return Ok(bad_gateway(client, &*e)?),
+        };
+        let framing = match response_framing(&mut headers) {
+            Ok(framing) => framing,
+            Err(e) => return Ok(bad_gateway(client, &e)?),
+        };
+
+        let mut head = format!(
+            "{} {} {}\r\n",
+            request.get_http_version().as_str(),
+            status_line.get_status_code(),
+            status_line.get_reason()
+        );
+        for (name, value) in end_to_end(&headers) {
+            head += &format!("{name}: {value}\r\n");
+        }
+        // HTTP/1.0 has no chunked coding, so a 1.0 client gets the body
+        // decoded and ended by closing the connection.
+        let dechunk = matches!(framing, Framing::Chunked)
+            && matches!(request.get_http_version(), HttpVersion::Http1_0);
+        if matches!(framing, Framing::Chunked) && !dechunk {
+            head += "Transfer-Encoding: chunked\r\n";
+        }
+        // The client connection is not reused once the response is relayed.
+        head += "Connection: close\r\n\r\n";
+        client.write_all(head.as_bytes())?;
+
+        // The framing headers of a response to `HEAD` describe the body a
+        // `GET` would have had, but none follows.
+        if *request.get_method() == HttpRequestMethod::Head {
+            client.flush()?;
+            return Ok(());
+        }
+        match framing {
+            Framing::Length(length) => {
+                io::copy(&mut (&mut upstream).take(length), client)?;
+            }
+            Framing::Chunked if dechunk => copy_dechunked(&mut upstream, client, limits)?,
+            Framing::Chunked => copy_chunked(&mut upstream, client, limits)?,
+            // The upstream closes the connection after the body because we
+            // asked for `Connection: close`.
+            Framing::Close => {
+                io::copy(&mut upstream, client)?;
+            }
+        }
+        client.flush()?;
+        Ok(())
+    }
+
+    /// Works out where `request` goes and the origin-form target to send.
+    fn destination<'a>(&'a self, request: &'a HttpRequest) -> Result<Destination<'a>, HttpError> {
+        match &self.upstream {
+            Upstream::Fixed(upstream) => Ok(Destination {
+                address: upstream.clone(),
+                host: upstream,
+                path: request.get_path(),
+            }),
+            Upstream::FromTarget => {
+                let (authority, path) = split_url(request.get_path())?;
+                Ok(Destination {
+                    address: with_default_port(authority),
+                    host: authority,
+                    path,
+                })
+            }
+        }
+    }
+
+    /// Sends the request head and `body` upstream and reads back the
+    /// response head, leaving the response body unread in the returned
+    /// reader.
+    fn open_upstream<R: BufRead>(
+        &self,
+        request: &HttpRequest,
+        body: BodyDecoder,
+        destination: &Destination,
+        client: &mut R,
+        peer_addr: Option<SocketAddr>,
+        config: &ServerConfig,
+    ) -> Result<UpstreamResponse, Box<dyn std::error::Error>> {
+        let mut upstream = TcpStream::connect(&destination.address)?;
+
+        let request_line = HttpRequestLine::new(
+            HttpVersion::Http1_1,
+            destination.path.to_string(),
+            request.get_method().to_owned(),
+        );
+        let mut head = format!("{request_line}\r\n");
+        let mut forwarded_for = None;
+        for (name, value) in end_to_end(request.get_headers()) {
+            if name.eq_ignore_ascii_case("Host") {
+                continue;
+            }
+            if name.eq_ignore_ascii_case("X-Forwarded-For") {
+                forwarded_for = Some(value.to_string());
+                continue;
+            }
+            head += &format!("{name}: {value}\r\n");
+        }
+        head += &format!("Host: {}\r\n", destination.host);
+        if let Some(peer_addr) = peer_addr {
+            let client_ip = peer_addr.ip().to_string();
+            let forwarded_for = match forwarded_for {
+                Some(previous) => format!("{previous}, {client_ip}"),
+                None => client_ip,
+            };
+            head += &format!("X-Forwarded-For: {forwarded_for}\r\n");
+        }
+        if matches!(body, BodyDecoder::Chunked { .. }) {
+            head += "Transfer-Encoding: chunked\r\n";
+        }
+        head += "Connection: close\r\n\r\n";
+        upstream.write_all(head.as_bytes())?;
+
+        match body {
+            BodyDecoder::Length(length) => {
+                let copied = io::copy(&mut client.take(length), &mut upstream)?;
+                if copied != length {
+                    return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+                }
+            }
+            BodyDecoder::Chunked { limits, .. } => copy_chunked(client, &mut upstream, limits)?,
+            BodyDecoder::Done => {}
+        }
+        upstream.flush()?;
+
+        let mut upstream = BufReader::new(upstream);
+        // Interim responses, such as the `100 Continue` an upstream sends
+        // for a forwarded `Expect`, precede the final one. A `101` is final,
+        // as the connection then speaks another protocol.
+        loop {
+            let status_line = HttpResponseStatusLine::from_stream(&mut upstream)?;
+            let headers = read_headers(&mut upstream, config)?;
+            let status_code = status_line.get_status_code();
+            if !(100..200).contains(&status_code) || status_code == 101 {
+                return Ok(UpstreamResponse {
+                    reader: upstream,
+                    status_line,
+                    headers,
+                });
+            }
+        }
+    }
+}
+
+/// Whether `name` is one of the hop-by-hop headers defined by RFC 7230,
+/// which apply to a single connection and must not be forwarded.
+///
+/// Headers named in a message's `Connection` header are hop-by-hop as well;
+/// [`end_to_end`] accounts for those.
+pub fn is_hop_by_hop(name: &str) -> bool {
+    HOP_BY_HOP_HEADERS
+        .iter()
+        .any(|hop_by_hop| name.eq_ignore_ascii_case(hop_by_hop))
+}
+
+/// The headers of a message that should be relayed to the next hop.
+///
+/// ```
+/// use http_server_starter_rust::http::proxy::end_to_end;
+///
+/// let headers: Vec<(String, String)> = [
+///     ("Host", "example.com"),
+///     ("Connection", "X-Custom"),
+///     ("x-custom", "1"),
+///     ("Keep-Alive", "timeout=5"),
+///     ("Accept", "*/*"),
+/// ]
+/// .iter()
+/// .map(|(name, value)| (name.to_string(), value.to_string()))
+/// .collect();
+/// let forwarded: Vec<_> = end_to_end(&headers).collect();
+/// assert_eq!(forwarded, [("Host", "example.com"), ("Accept", "*/*")]);
+/// ```
+pub fn end_to_end(headers: &[(String, String)]) -> impl Iterator<Item = (&str, &str)> {
+    let connection_options = connection_options(headers);
+
+    headers
+        .iter()
+        .filter(move |(name, _)| {
+            !is_hop_by_hop(name)
+                && !connection_options
+                    .iter()
+                    .any(|option| name.eq_ignore_ascii_case(option))
+        })
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+}
+
+/// Works out how a response with `headers` delimits its body (RFC 7230
+/// §3.3.3), refusing a `Content-Length` that is malformed, conflicts with
+/// another or comes with `Transfer-Encoding`, which the client could read
+/// differently.
+fn response_framing(headers: &mut Vec<(String, String)>) -> Result<Framing, HttpError> {
+    check_body_framing(headers)?;
+    if find_header(headers, "Transfer-Encoding").is_some() {
+        if is_chunked(headers) {
+            return Ok(Framing::Chunked);
+        }
+        return Ok(Framing::Close);
+    }
+    match find_header(headers, "Content-Length") {
+        Some(length) => parse_content_length(length).map(Framing::Length),
+        None => Ok(Framing::Close),
+    }
+}
+
+fn is_chunked(headers: &[(String, String)]) -> bool {
+    find_header(headers, "Transfer-Encoding").is_some_and(|encoding| {
+        encoding
+            .rsplit(',')
+            .next()
+            .is_some_and(|last| last.trim().eq_ignore_ascii_case("chunked"))
+    })
+}
+
+/// Relays the data of a chunked body without its framing, dropping any
+/// trailers.
+fn copy_dechunked<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    limits: ChunkLimits,
+) -> io::Result<()> {
+    let mut decoder = BodyDecoder::Chunked {
+        remaining: 0,
+        limits,
+    };
+    let mut buffer = [0; 8 * 1024];
+    loop {
+        match decoder.read(reader, &mut buffer)? {
+            0 => return Ok(()),
+            read => writer.write_all(&buffer[..read])?,
+        }
+    }
+}
+
+/// Relays a chunked body unchanged, stopping after the last chunk and its
+/// trailer section, whose lines are bounded by `limits`.
+fn copy_chunked<R: BufRead, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    limits: ChunkLimits,
+) -> io::Result<()> {
+    loop {
+        let (size_line, size) = read_chunk_size(reader, limits.size_line_bytes)?;
+        writer.write_all(&size_line)?;
+        writer.write_all(b"\r\n")?;
+
+        if size == 0 {
+            break;
+        }
+        let copied = io::copy(&mut reader.take(size), writer)?;
+        if copied != size {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        read_chunk_end(reader)?;
+        writer.write_all(b"\r\n")?;
+    }
+    read_trailers(reader, limits, |trailer| {
+        writer.write_all(trailer)?;
+        writer.write_all(b"\r\n")
+    })?;
+    writer.write_all(b"\r\n")
+}
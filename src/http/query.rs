@@ -0,0 +1,47 @@
+/// Parses an `application/x-www-form-urlencoded` string such as a query
+/// string into its key-value pairs, in order and with repeated keys kept.
+///
+/// Keys and values are percent-decoded and `+` is read as a space. A pair
+/// without `=` has an empty value.
+pub fn parse_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode_component(key), decode_component(value))
+        })
+        .collect()
+}
+
+/// Decodes `%XX` escapes. Malformed escapes are kept as they are, and
+/// decoded bytes that are not valid UTF-8 are replaced with U+FFFD.
+pub fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = match bytes[index] {
+            b'%' => bytes
+                .get(index + 1..index + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn decode_component(component: &str) -> String {
+    percent_decode(&component.replace('+', " "))
+}
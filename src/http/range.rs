@@ -0,0 +1,279 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    request::{HttpRequest, HttpRequestMethod},
+    response::{HttpResponse, HttpStatus},
+};
+
+/// More ranges than this in one request are treated as abuse and the whole
+/// representation is sent instead.
+const MAX_RANGES: usize = 16;
+
+/// An inclusive span of bytes within a representation.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// The number of bytes in the range.
+    fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    fn content_range(&self, length: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, length)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum RangeSelection {
+    /// No usable `Range` header; send the whole representation.
+    Full,
+    /// The satisfiable ranges, sorted and with overlapping or adjacent
+    /// ranges coalesced.
+    Ranges(Vec<ByteRange>),
+    /// None of the requested ranges overlap the representation.
+    Unsatisfiable,
+}
+
+/// Interprets a `Range` header for a representation of `length` bytes.
+///
+/// Per RFC 7233 a header that cannot be parsed is ignored rather than
+/// rejected, so it selects the full representation.
+pub fn select_ranges(header: Option<&str>, length: u64) -> RangeSelection {
+    let Some(specs) = header.and_then(|header| {
+        let (unit, specs) = header.trim().split_once('=')?;
+        unit.eq_ignore_ascii_case("bytes").then_some(specs)
+    }) else {
+        return RangeSelection::Full;
+    };
+
+    let mut ranges = Vec::new();
+    for (count, spec) in specs.split(',').map(str::trim).enumerate() {
+        if count == MAX_RANGES {
+            return RangeSelection::Full;
+        }
+        match parse_spec(spec, length) {
+            Ok(Some(range)) => ranges.push(range),
+            Ok(None) => {}
+            Err(()) => return RangeSelection::Full,
+        }
+    }
+    if ranges.is_empty() {
+        return RangeSelection::Unsatisfiable;
+    }
+
+    ranges.sort_by_key(|range| range.start);
+    let mut coalesced: Vec<ByteRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match coalesced.last_mut() {
+            Some(last) if range.start <= last.end + 1 => last.end = last.end.max(range.end),
+            _ => coalesced.push(range),
+        }
+    }
+    RangeSelection::Ranges(coalesced)
+}
+
+/// Parses one `first-last`, `first-` or `-suffix` spec. Returns `Ok(None)`
+/// for a well-formed spec that lies outside the representation.
+fn parse_spec(spec: &str, length: u64) -> Result<Option<ByteRange>, ()> {
+    let (first, last) = spec.split_once('-').ok_or(())?;
+    let parse = |value: &str| -> Result<u64, ()> {
+        if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(());
+        }
+        value.parse().map_err(|_| ())
+    };
+
+    if first.is_empty() {
+        let suffix = parse(last)?;
+        if suffix == 0 || length == 0 {
+            return Ok(None);
+        }
+        return Ok(Some(ByteRange {
+            start: length.saturating_sub(suffix),
+            end: length - 1,
+        }));
+    }
+
+    let start = parse(first)?;
+    let end = match last {
+        "" => u64::MAX,
+        last => parse(last)?,
+    };
+    if end < start {
+        return Err(());
+    }
+    if start >= length {
+        return Ok(None);
+    }
+    Ok(Some(ByteRange {
+        start,
+        end: end.min(length - 1),
+    }))
+}
+
+/// Builds a response with the parts of `data` selected by the request's
+/// `Range` header: `200` with everything, `206` with a single range or a
+/// `multipart/byteranges` body, or `416` if nothing is satisfiable.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use http_server_starter_rust::http::{
+///     config::ServerConfig, range::ranged_response, request::HttpRequest,
+/// };
+///
+/// let raw = "GET / HTTP/1.1\r\nrange: bytes=2-4\r\n\r\n";
+/// let request = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())?;
+/// let response = ranged_response(&request, b"0123456789", "text/plain");
+/// assert_eq!(response.get_status_code(), 206);
+/// assert_eq!(response.get_header("Content-Range"), Some("bytes 2-4/10"));
+/// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+/// ```
+pub fn ranged_response(request: &HttpRequest, data: &[u8], content_type: &str) -> HttpResponse {
+    let length = data.len() as u64;
+    let mut response = range_response_head(request);
+    let slice = |range: &ByteRange| &data[range.start as usize..=range.end as usize];
+
+    match select_ranges(range_header(request), length) {
+        RangeSelection::Full => {
+            response
+                .add_header("Content-Type", content_type)
+                .append_bytes(data);
+        }
+        RangeSelection::Unsatisfiable => {
+            set_unsatisfiable(&mut response, length);
+        }
+        RangeSelection::Ranges(ranges) if ranges.len() == 1 => {
+            set_single_range(&mut response, &ranges[0], length, content_type)
+                .append_bytes(slice(&ranges[0]));
+        }
+        RangeSelection::Ranges(ranges) => {
+            let boundary = boundary();
+            set_multipart(&mut response, &boundary);
+            for range in &ranges {
+                response
+                    .append_content(&part_head(&boundary, content_type, range, length))
+                    .append_bytes(slice(range))
+                    .append_content("\r\n");
+            }
+            response.append_content(&format!("--{boundary}--\r\n"));
+        }
+    }
+    response
+}
+
+/// Like [`ranged_response`], but streams the selected parts of `file`
+/// instead of reading it into memory, seeking to each range in turn.
+pub fn ranged_file_response(
+    request: &HttpRequest,
+    mut file: File,
+    content_type: &str,
+) -> io::Result<HttpResponse> {
+    let length = file.metadata()?.len();
+    let mut response = range_response_head(request);
+
+    match select_ranges(range_header(request), length) {
+        RangeSelection::Full => {
+            response
+                .add_header("Content-Type", content_type)
+                .stream_file(file, length);
+        }
+        RangeSelection::Unsatisfiable => {
+            set_unsatisfiable(&mut response, length);
+        }
+        RangeSelection::Ranges(ranges) if ranges.len() == 1 => {
+            file.seek(SeekFrom::Start(ranges[0].start))?;
+            set_single_range(&mut response, &ranges[0], length, content_type)
+                .stream_file(file, ranges[0].len());
+        }
+        RangeSelection::Ranges(ranges) => {
+            let boundary = boundary();
+            set_multipart(&mut response, &boundary);
+            let parts: Vec<(String, ByteRange)> = ranges
+                .into_iter()
+                .map(|range| (part_head(&boundary, content_type, &range, length), range))
+                .collect();
+            let trailer = format!("--{boundary}--\r\n");
+            let body_length = parts
+                .iter()
+                .map(|(head, range)| head.len() as u64 + range.len() + 2)
+                .sum::<u64>()
+                + trailer.len() as u64;
+
+            response.stream_sized(body_length, move |writer| {
+                for (head, range) in parts {
+                    writer.write_all(head.as_bytes())?;
+                    file.seek(SeekFrom::Start(range.start))?;
+                    let copied = io::copy(&mut (&mut file).take(range.len()), writer)?;
+                    if copied < range.len() {
+                        return Err(io::ErrorKind::UnexpectedEof.into());
+                    }
+                    writer.write_all(b"\r\n")?;
+                }
+                writer.write_all(trailer.as_bytes())
+            });
+        }
+    }
+    Ok(response)
+}
+
+fn range_response_head(request: &HttpRequest) -> HttpResponse {
+    let mut response = HttpResponse::new(request.get_http_version().to_owned());
+    response.add_header("Accept-Ranges", "bytes");
+    response
+}
+
+/// The `Range` header, which is only honoured on `GET` requests.
+fn range_header(request: &HttpRequest) -> Option<&str> {
+    match request.get_method() {
+        HttpRequestMethod::Get => request.get_header("Range"),
+        _ => None,
+    }
+}
+
+fn set_unsatisfiable(response: &mut HttpResponse, length: u64) -> &mut HttpResponse {
+    response
+        .set_status(HttpStatus::RangeNotSatisfiable)
+        .add_header("Content-Range", &format!("bytes */{length}"))
+}
+
+fn set_single_range<'a>(
+    response: &'a mut HttpResponse,
+    range: &ByteRange,
+    length: u64,
+    content_type: &str,
+) -> &'a mut HttpResponse {
+    response
+        .set_status(HttpStatus::PartialContent)
+        .add_header("Content-Type", content_type)
+        .add_header("Content-Range", &range.content_range(length))
+}
+
+fn set_multipart<'a>(response: &'a mut HttpResponse, boundary: &str) -> &'a mut HttpResponse {
+    response.set_status(HttpStatus::PartialContent).add_header(
+        "Content-Type",
+        &format!("multipart/byteranges; boundary={boundary}"),
+    )
+}
+
+fn part_head(boundary: &str, content_type: &str, range: &ByteRange, length: u64) -> String {
+    format!(
+        "--{boundary}\r\nContent-Type: {content_type}\r\nContent-Range: {}\r\n\r\n",
+        range.content_range(length)
+    )
+}
+
+fn boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    format!("byteranges-{nanos:x}")
+}
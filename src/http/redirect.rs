@@ -0,0 +1,138 @@
+use super::{
+    request::HttpRequest,
+    response::{HttpResponse, HttpStatus},
+};
+
+/// How [`redirect`] writes the `Location` header.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LocationStyle {
+    /// Send the location unchanged. RFC 7231 allows relative references.
+    AsGiven,
+    /// Resolve relative locations against the request's `Host` header, for
+    /// older clients that only follow absolute URLs.
+    Absolute,
+}
+
+/// Builds a redirect to `location` with the given 3xx `status`.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use http_server_starter_rust::http::{
+///     config::ServerConfig,
+///     redirect::{redirect, LocationStyle},
+///     request::HttpRequest,
+///     response::HttpStatus,
+/// };
+///
+/// let raw = "GET /account/settings HTTP/1.1\r\nHost: example.com:8080\r\n\r\n";
+/// let request = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())?;
+/// let location = |location: &str, style: LocationStyle| {
+///     let response = redirect(&request, location, HttpStatus::Found, style).to_bytes();
+///     let response = String::from_utf8(response).unwrap();
+///     let (_, rest) = response.split_once("Location: ").unwrap();
+///     rest.split("\r\n").next().unwrap().to_string()
+/// };
+///
+/// assert_eq!(location("/login", LocationStyle::AsGiven), "/login");
+/// assert_eq!(location("/login", LocationStyle::Absolute), "http://example.com:8080/login");
+/// assert_eq!(location("profile", LocationStyle::Absolute), "http://example.com:8080/account/profile");
+/// assert_eq!(location("https://example.org/", LocationStyle::Absolute), "https://example.org/");
+/// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+/// ```
+pub fn redirect(
+    request: &HttpRequest,
+    location: &str,
+    status: HttpStatus,
+    style: LocationStyle,
+) -> HttpResponse {
+    let location = match style {
+        LocationStyle::AsGiven => location.to_string(),
+        LocationStyle::Absolute => absolute_location(request, location),
+    };
+    let mut response = HttpResponse::new(request.get_http_version().to_owned());
+    response
+        .set_status(status)
+        .add_header("Location", &location);
+    response
+}
+
+/// Resolves `location` into an absolute URL using the request's `Host`
+/// header and path. Locations that are already absolute, or requests
+/// without a `Host` header, leave it unchanged.
+///
+/// The server only speaks plain HTTP, so the scheme is always `http`.
+pub fn absolute_location(request: &HttpRequest, location: &str) -> String {
+    let has_scheme = location
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && !scheme.contains(['/', '?', '#']));
+    if has_scheme {
+        return location.to_string();
+    }
+    if location.starts_with("//") {
+        return format!("http:{location}");
+    }
+    let Some(host) = request.get_host() else {
+        return location.to_string();
+    };
+
+    if location.starts_with('/') {
+        return format!("http://{host}{location}");
+    }
+    let path = request.get_path();
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+    let directory = path.rsplit_once('/').map_or("", |(directory, _)| directory);
+    format!("http://{host}{directory}/{location}")
+}
+
+/// Answers a request sent with `Upgrade-Insecure-Requests: 1` with a
+/// `301` to the same path over `https`, or returns `None` for requests
+/// that did not ask for it or have no `Host` header.
+///
+/// The port the request came in on serves plain HTTP, so it is dropped
+/// and the redirect goes to the default HTTPS port.
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use http_server_starter_rust::http::{
+///     config::ServerConfig, redirect::upgrade_insecure, request::HttpRequest,
+/// };
+///
+/// let upgrade = |headers: &str| {
+///     let raw = format!("GET /search?q=rust HTTP/1.1\r\n{headers}\r\n");
+///     let request = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())?;
+///     let response = upgrade_insecure(&request).map(|response| response.to_bytes());
+///     Ok::<_, std::io::Error>(response.map(|bytes| String::from_utf8(bytes).unwrap()))
+/// };
+///
+/// let response = upgrade("Host: example.com:8080\r\nUpgrade-Insecure-Requests: 1\r\n")?.unwrap();
+/// assert!(response.starts_with("HTTP/1.1 301 Moved Permanently\r\n"));
+/// assert!(response.contains("\r\nLocation: https://example.com/search?q=rust\r\n"));
+/// assert!(upgrade("Host: example.com\r\n")?.is_none());
+/// assert!(upgrade("host: example.com\r\nupgrade-insecure-requests: 1\r\n")?.is_some());
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn upgrade_insecure(request: &HttpRequest) -> Option<HttpResponse> {
+    if request
+        .get_header("Upgrade-Insecure-Requests")
+        .map(str::trim)
+        != Some("1")
+    {
+        return None;
+    }
+    let (host, _) = request.host_and_port()?;
+    let location = if host.contains(':') {
+        format!("https://[{host}]{}", request.get_path())
+    } else {
+        format!("https://{host}{}", request.get_path())
+    };
+    let mut response = redirect(
+        request,
+        &location,
+        HttpStatus::MovedPermanently,
+        LocationStyle::AsGiven,
+    );
+    response.add_header("Vary", "Upgrade-Insecure-Requests");
+    Some(response)
+}
@@ -1,40 +1,74 @@
 use std::{
-    io::{BufReader, Error, ErrorKind, Read},
+    borrow::Cow,
+    fmt,
+    io::{self, BufRead, BufReader, Error, ErrorKind, Read, Write},
     net::TcpStream,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant},
 };
 
-use super::{errors::HttpError, HttpVersion};
+use super::{
+    body::{BodyDecoder, BodyReader, ChunkLimits},
+    config::{LineEndings, LineFolding, ServerConfig},
+    deserialize::FromRequestBody,
+    errors::HttpError,
+    negotiation,
+    path::{canonicalize_path, merge_slashes},
+    query::parse_query,
+    response::{HttpResponse, HttpStatus},
+    HttpVersion,
+};
 
-#[derive(Clone)]
+/// Writes an interim `1xx` response to the connection a request came in on.
+pub(crate) type InterimWriter = Box<dyn FnMut(&HttpResponse) -> io::Result<()> + Send>;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub enum HttpRequestMethod {
     Get,
+    Head,
     Post,
     Delete,
     Patch,
     Put,
+    Options,
 }
 impl HttpRequestMethod {
     fn from_str(str: &str) -> Result<Self, HttpError> {
         match str {
             "GET" => Ok(HttpRequestMethod::Get),
+            "HEAD" => Ok(HttpRequestMethod::Head),
             "POST" => Ok(HttpRequestMethod::Post),
             "DELETE" => Ok(HttpRequestMethod::Delete),
             "PATCH" => Ok(HttpRequestMethod::Patch),
             "PUT" => Ok(HttpRequestMethod::Put),
+            "OPTIONS" => Ok(HttpRequestMethod::Options),
 
             _ => Err(HttpError::UnknownMethodError(str.to_string())),
         }
     }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpRequestMethod::Get => "GET",
+            HttpRequestMethod::Head => "HEAD",
+            HttpRequestMethod::Post => "POST",
+            HttpRequestMethod::Delete => "DELETE",
+            HttpRequestMethod::Patch => "PATCH",
+            HttpRequestMethod::Put => "PUT",
+            HttpRequestMethod::Options => "OPTIONS",
+        }
+    }
 }
 
-struct HttpRequestLine {
+#[derive(Clone)]
+pub(crate) struct HttpRequestLine {
     version: HttpVersion,
     target: String,
     method: HttpRequestMethod,
 }
 
 impl HttpRequestLine {
-    fn new(version: HttpVersion, target: String, method: HttpRequestMethod) -> Self {
+    pub(crate) fn new(version: HttpVersion, target: String, method: HttpRequestMethod) -> Self {
         Self {
             version,
             target,
@@ -42,100 +76,688 @@ impl HttpRequestLine {
         }
     }
 
-    fn from_stream(stream: &mut BufReader<&TcpStream>) -> Result<Self, Box<dyn std::error::Error>> {
-        let buffer = parse_stream_untill_sequence(stream, b"\r\n")?;
+    /// Parses a request line from `stream`, reading it into `buffer`.
+    fn from_stream<R: BufRead>(
+        stream: &mut R,
+        config: &ServerConfig,
+        buffer: &mut Vec<u8>,
+    ) -> Result<Self, HttpError> {
+        read_line_into(stream, config, buffer)?;
+        let buffer = &*buffer;
         let mut words = buffer.split(|byte| byte == &b' ');
 
-        let mut method = String::new();
-        let mut target = String::new();
-        let mut version = String::new();
+        let malformed = || {
+            let line = String::from_utf8_lossy(buffer).into_owned();
+            HttpError::MalformedRequestLine(line)
+        };
 
-        words.next().unwrap().read_to_string(&mut method)?;
-        words.next().unwrap().read_to_string(&mut target)?;
-        words.next().unwrap().read_to_string(&mut version)?;
+        // An empty target, as in `GET  HTTP/1.1`, splits into an empty word.
+        let (Some(method), Some(target), Some(version), None) =
+            (words.next(), words.next(), words.next(), words.next())
+        else {
+            return Err(malformed());
+        };
+        // Only the target is kept as text; the method and version are
+        // parsed from the line in place.
+        let method = std::str::from_utf8(method).map_err(|_| malformed())?;
+        let target = String::from_utf8(target.to_vec())?;
+        let version = std::str::from_utf8(version).map_err(|_| malformed())?;
 
-        let method = HttpRequestMethod::from_str(&method)?;
-        let version = HttpVersion::from_str(&version)?;
+        let method = HttpRequestMethod::from_str(method)?;
+        let version = match version.parse::<HttpVersion>()? {
+            HttpVersion::Unsupported(major, minor) => {
+                return Err(HttpError::UnsupportedHttpVersion(major, minor))
+            }
+            version => version,
+        };
+        // `*` addresses the server as a whole and only makes sense for
+        // `OPTIONS`.
+        let valid_target = match target.as_str() {
+            "" => false,
+            "*" => method == HttpRequestMethod::Options,
+            _ => true,
+        };
+        if !valid_target {
+            return Err(malformed());
+        }
+        // Only origin-form targets such as `/a/b?c` have a path to resolve.
+        let target = if target.starts_with('/') {
+            let target = if config.merge_slashes {
+                merge_slashes(&target)
+            } else {
+                target
+            };
+            canonical_target(&target).ok_or(HttpError::PathEscapesRoot(target))?
+        } else {
+            target
+        };
         Ok(Self::new(version, target, method))
     }
 }
 
-fn parse_stream_untill_sequence(
-    stream: &mut BufReader<&TcpStream>,
-    sequence: &[u8],
-) -> Result<Vec<u8>, Error> {
-    let mut buffer: Vec<u8> = Vec::new();
-    let mut sequence_buffer: Vec<u8> = Vec::with_capacity(sequence.len());
+/// An origin-form target with its path canonicalized and any query left
+/// as it is.
+fn canonical_target(target: &str) -> Option<String> {
+    match target.split_once('?') {
+        Some((path, query)) => Some(format!("{}?{}", canonicalize_path(path)?, query)),
+        None => canonicalize_path(target),
+    }
+}
 
-    for byte in stream.bytes() {
-        let byte = byte?;
+impl fmt::Display for HttpRequestLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.method.as_str(),
+            self.target,
+            self.version.as_str()
+        )
+    }
+}
 
-        buffer.push(byte);
-        sequence_buffer.push(byte);
+/// Reads one line, consuming it and its terminator but nothing after, so
+/// that the rest of the stream stays buffered for whoever reads next.
+pub(crate) fn read_line<R: BufRead + ?Sized>(
+    stream: &mut R,
+    config: &ServerConfig,
+) -> Result<Vec<u8>, HttpError> {
+    let mut line = Vec::new();
+    read_line_into(stream, config, &mut line)?;
+    Ok(line)
+}
 
-        if sequence_buffer.len() > sequence.len() {
-            sequence_buffer.remove(0);
+/// Like [`read_line`], but reads into `line`, replacing its contents, so
+/// that one buffer can be reused for many lines.
+fn read_line_into<R: BufRead + ?Sized>(
+    stream: &mut R,
+    config: &ServerConfig,
+    line: &mut Vec<u8>,
+) -> Result<(), HttpError> {
+    line.clear();
+    stream.read_until(b'\n', line)?;
+    if line.pop() != Some(b'\n') {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "Line ended by end of stream").into());
+    }
+    match line.last() {
+        Some(b'\r') => {
+            line.pop();
         }
+        _ if config.line_endings == LineEndings::Strict => {
+            let line = String::from_utf8_lossy(line).into_owned();
+            return Err(HttpError::BareLineFeed(line));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Whether `bytes` could be the start of a request line, as a pipelined
+/// request following one without a body would be.
+pub(crate) fn starts_request_line(bytes: &[u8]) -> bool {
+    let method = bytes.split(|byte| byte == &b' ').next().unwrap_or_default();
+    let complete = method.len() < bytes.len();
+    [
+        HttpRequestMethod::Get,
+        HttpRequestMethod::Head,
+        HttpRequestMethod::Post,
+        HttpRequestMethod::Delete,
+        HttpRequestMethod::Patch,
+        HttpRequestMethod::Put,
+        HttpRequestMethod::Options,
+    ]
+    .iter()
+    .map(|known| known.as_str().as_bytes())
+    .any(|known| {
+        if complete {
+            known == method
+        } else {
+            known.starts_with(method)
+        }
+    })
+}
+
+fn is_eof(err: &HttpError) -> bool {
+    matches!(err, HttpError::Io(err) if err.kind() == ErrorKind::UnexpectedEof)
+}
+
+pub(crate) fn read_headers<R: BufRead>(
+    stream: &mut R,
+    config: &ServerConfig,
+) -> Result<Vec<(String, String)>, HttpError> {
+    read_headers_into(stream, config, &mut Vec::with_capacity(256))
+}
+
+/// Like [`read_headers`], but reads every line into `line`, so that only
+/// the names and values kept are allocated.
+fn read_headers_into<R: BufRead>(
+    stream: &mut R,
+    config: &ServerConfig,
+    line: &mut Vec<u8>,
+) -> Result<Vec<(String, String)>, HttpError> {
+    let mut headers = Vec::new();
+    // Line 1 of the head is the request or status line.
+    for line_number in 2.. {
+        match read_line_into(stream, config, line) {
+            Ok(()) => {}
+            // A truncated request ends its headers at EOF in lenient mode.
+            Err(err) if config.line_endings == LineEndings::Lenient && is_eof(&err) => break,
+            Err(err) => return Err(err),
+        }
+        let header_str = std::str::from_utf8(line).map_err(|_| HttpError::MalformedHeader {
+            line: line_number,
+            header: String::from_utf8_lossy(line).into_owned(),
+        })?;
+        if header_str.starts_with([' ', '\t']) {
+            let previous = headers
+                .last_mut()
+                .filter(|_| config.line_folding == LineFolding::Unfold);
+            let Some((_, value)) = previous else {
+                return Err(HttpError::FoldedHeader {
+                    line: line_number,
+                    header: header_str.to_string(),
+                });
+            };
+            let continuation = header_str.trim();
+            if !continuation.is_empty() {
+                *value = format!("{} {}", value, continuation);
+            }
+            continue;
+        }
+        let header_str = header_str.trim();
+        if header_str.is_empty() {
+            break;
+        }
+        if headers.len() == config.max_headers {
+            return Err(HttpError::TooManyHeaders);
+        }
+        // Whitespace is optional after the colon, but not allowed before it.
+        let parsed = header_str
+            .split_once(':')
+            .filter(|(name, _)| !name.is_empty() && !name.ends_with([' ', '\t']));
+        let Some((header_name, header_value)) = parsed else {
+            return Err(HttpError::MalformedHeader {
+                line: line_number,
+                header: header_str.to_string(),
+            });
+        };
+        let header_value = header_value.trim_matches([' ', '\t']);
+        headers.push((header_name.to_string(), header_value.to_string()));
+    }
+    Ok(headers)
+}
 
-        if sequence_buffer == sequence {
-            buffer.truncate(buffer.len() - sequence.len());
+/// The value of the first of `headers` named `header_name`, ignoring case.
+pub(crate) fn find_header<'a>(
+    headers: &'a [(String, String)],
+    header_name: &str,
+) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(header_name))
+        .map(|(_, value)| value.as_str())
+}
 
-            return Ok(buffer);
+/// The options listed by the `Connection` headers among `headers`,
+/// lowercased, since both they and header names are case-insensitive.
+pub(crate) fn connection_options(headers: &[(String, String)]) -> Vec<String> {
+    headers
+        .iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Connection"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(str::trim)
+        .filter(|option| !option.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
+
+/// Parses a `Content-Length` value, which RFC 7230 restricts to decimal
+/// digits: signs, whitespace and anything else are rejected.
+pub(crate) fn parse_content_length(value: &str) -> Result<u64, HttpError> {
+    let invalid = || HttpError::InvalidContentLength(value.to_string());
+    if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    value.parse().map_err(|_| invalid())
+}
+
+/// Rejects headers that frame a body ambiguously, which intermediaries may
+/// disagree on and so could be used to smuggle requests (RFC 7230 §3.3.3).
+/// Repeated `Content-Length` headers with the same value are collapsed into
+/// the first.
+pub(crate) fn check_body_framing(headers: &mut Vec<(String, String)>) -> Result<(), HttpError> {
+    let is_content_length = |name: &str| name.eq_ignore_ascii_case("Content-Length");
+    let mut lengths = headers
+        .iter()
+        .filter(|(name, _)| is_content_length(name))
+        .map(|(_, value)| value.trim());
+    if let Some(first) = lengths.next() {
+        if let Some(other) = lengths.find(|value| value != &first) {
+            return Err(HttpError::ConflictingContentLength(
+                first.to_string(),
+                other.to_string(),
+            ));
+        }
+        if headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("Transfer-Encoding"))
+        {
+            return Err(HttpError::ConflictingBodyLength);
         }
     }
-    return Err(Error::new(ErrorKind::Other, "No sequence found in stream"));
+
+    let mut seen = false;
+    headers.retain(|(name, _)| {
+        let duplicate = seen && is_content_length(name);
+        seen |= is_content_length(name);
+        !duplicate
+    });
+    Ok(())
 }
 
+#[derive(Clone)]
 pub struct HttpRequest {
     request_line: HttpRequestLine,
     headers: Vec<(String, String)>,
-    body: Option<String>,
+    query: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+    body_reader: Arc<Mutex<Option<BodyReader>>>,
+    interim_writer: Arc<Mutex<Option<InterimWriter>>>,
+    deadline: Option<Instant>,
+    chunk_limits: ChunkLimits,
+    head_len: usize,
 }
 
 impl HttpRequest {
-    pub fn from_stream(stream: &TcpStream) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut stream = BufReader::new(stream);
+    pub fn from_stream(stream: &TcpStream, config: &ServerConfig) -> Result<Self, HttpError> {
+        Self::from_reader(&mut BufReader::new(stream), config)
+    }
 
-        let request_line = HttpRequestLine::from_stream(&mut stream)?;
-        let mut headers = Vec::new();
-        let header_str = String::from_utf8(parse_stream_untill_sequence(&mut stream, b"\r\n")?)?;
-        let header_str = header_str.trim();
-        if header_str.len() > 0 {
-            let mut parts = header_str.split(": ");
-            let header_name = parts.next().unwrap();
-            let header_value = parts.next().unwrap();
-            headers.push((header_name.to_string(), header_value.to_string()));
-        }
-        loop {
-            let header_str =
-                String::from_utf8(parse_stream_untill_sequence(&mut stream, b"\r\n")?)?;
-            let header_str = header_str.trim();
-            if header_str.len() == 0 {
-                break;
-            }
-            let mut parts = header_str.split(": ");
-            let header_name = parts.next().unwrap();
-            let header_value = parts.next().unwrap();
-            headers.push((header_name.to_string(), header_value.to_string()));
+    /// Parses a request head from `stream`, leaving any body unread.
+    ///
+    /// A request line with an empty target, or with the `*` target for any
+    /// method but `OPTIONS`, is malformed:
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// };
+    ///
+    /// let parse = |raw: &str| {
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())
+    /// };
+    /// for raw in ["GET  HTTP/1.1\r\n\r\n", "GET * HTTP/1.1\r\n\r\n"] {
+    ///     assert!(matches!(parse(raw), Err(HttpError::MalformedRequestLine(_))));
+    /// }
+    /// assert_eq!(parse("OPTIONS * HTTP/1.1\r\n\r\n")?.get_path(), "*");
+    /// # Ok::<(), HttpError>(())
+    /// ```
+    ///
+    /// Requests that frame their body with both `Content-Length` and
+    /// `Transfer-Encoding` are rejected:
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// };
+    ///
+    /// let raw = "POST / HTTP/1.1\r\n\
+    ///            Content-Length: 5\r\n\
+    ///            Transfer-Encoding: chunked\r\n\
+    ///            \r\n";
+    /// let result = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default());
+    /// assert!(matches!(result, Err(HttpError::ConflictingBodyLength)));
+    /// ```
+    ///
+    /// as are repeated `Content-Length` headers that disagree. Repeats with
+    /// the same value are collapsed into one:
+    ///
+    /// ```
+    /// # use std::io::Cursor;
+    /// # use http_server_starter_rust::http::{
+    /// #     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// # };
+    /// let parse = |lengths: [&str; 2]| {
+    ///     let raw = format!(
+    ///         "POST / HTTP/1.1\r\nContent-Length: {}\r\nContent-Length: {}\r\n\r\n",
+    ///         lengths[0], lengths[1]
+    ///     );
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())
+    /// };
+    ///
+    /// let request = parse(["5", "5"])?;
+    /// assert_eq!(request.get_headers(), [("Content-Length".to_string(), "5".to_string())]);
+    /// assert!(matches!(
+    ///     parse(["5", "6"]),
+    ///     Err(HttpError::ConflictingContentLength(_, _))
+    /// ));
+    /// # Ok::<(), HttpError>(())
+    /// ```
+    ///
+    /// The path is canonicalized with [`canonicalize_path`] before routing,
+    /// and a path that climbs above the root is rejected:
+    ///
+    /// ```
+    /// # use std::io::Cursor;
+    /// # use http_server_starter_rust::http::{
+    /// #     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// # };
+    /// let parse = |raw: &str| {
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())
+    /// };
+    /// let request = parse("GET /files/./a/../b?c=../d HTTP/1.1\r\n\r\n")?;
+    /// assert_eq!(request.get_path(), "/files/b?c=../d");
+    /// assert!(matches!(
+    ///     parse("GET /files/../../etc/passwd HTTP/1.1\r\n\r\n"),
+    ///     Err(HttpError::PathEscapesRoot(_))
+    /// ));
+    /// # Ok::<(), HttpError>(())
+    /// ```
+    ///
+    /// Whitespace around a header value is optional and not part of it,
+    /// but a header name must be followed directly by its colon:
+    ///
+    /// ```
+    /// # use std::io::Cursor;
+    /// # use http_server_starter_rust::http::{
+    /// #     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// # };
+    /// let parse = |raw: &str| {
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())
+    /// };
+    /// let request = parse("GET / HTTP/1.1\r\nHost:example.com\r\nX-Tab:\t a b \t\r\n\r\n")?;
+    /// assert_eq!(request.get_host(), Some("example.com"));
+    /// assert_eq!(request.get_header("X-Tab").unwrap(), "a b");
+    /// assert!(matches!(
+    ///     parse("GET / HTTP/1.1\r\nHost : example.com\r\n\r\n"),
+    ///     Err(HttpError::MalformedHeader { .. })
+    /// ));
+    /// # Ok::<(), HttpError>(())
+    /// ```
+    ///
+    /// A stream that ends before the blank line closing the head gives an
+    /// error rather than waiting for more:
+    ///
+    /// ```
+    /// # use std::io::{Cursor, ErrorKind};
+    /// # use http_server_starter_rust::http::{
+    /// #     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// # };
+    /// let heads = ["GET / HT", "GET / HTTP/1.1\r\nHost: exa", "GET / HTTP/1.1\r\nHost: a\r\n"];
+    /// for truncated in heads {
+    ///     let mut stream = Cursor::new(truncated);
+    ///     let parsed = HttpRequest::from_reader(&mut stream, &ServerConfig::default());
+    ///     assert!(
+    ///         matches!(&parsed, Err(HttpError::Io(e)) if e.kind() == ErrorKind::UnexpectedEof),
+    ///         "{truncated:?}"
+    ///     );
+    /// }
+    /// ```
+    pub fn from_reader<R: BufRead>(
+        stream: &mut R,
+        config: &ServerConfig,
+    ) -> Result<Self, HttpError> {
+        Self::from_reader_with(stream, config, &mut Vec::with_capacity(256))
+    }
+
+    /// Like [`from_reader`](Self::from_reader), but reads the lines of the
+    /// head into `scratch`, whose contents are replaced. A connection that
+    /// passes the same buffer for every request allocates it only once.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let mut stream = Cursor::new("GET /a HTTP/1.1\r\n\r\nGET /b HTTP/1.1\r\nHost: b\r\n\r\n");
+    /// let mut scratch = Vec::new();
+    /// let config = ServerConfig::default();
+    /// let first = HttpRequest::from_reader_with(&mut stream, &config, &mut scratch)?;
+    /// let second = HttpRequest::from_reader_with(&mut stream, &config, &mut scratch)?;
+    /// assert_eq!((first.get_path(), second.get_path()), ("/a", "/b"));
+    /// assert_eq!(second.get_host(), Some("b"));
+    /// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+    /// ```
+    pub fn from_reader_with<R: BufRead>(
+        stream: &mut R,
+        config: &ServerConfig,
+        scratch: &mut Vec<u8>,
+    ) -> Result<Self, HttpError> {
+        // Allowing one byte more than the limit tells a head that is too
+        // large apart from one that ends exactly at the limit.
+        let budget = config.max_request_head_bytes as u64 + 1;
+        let mut head = stream.take(budget);
+        let parsed =
+            HttpRequestLine::from_stream(&mut head, config, scratch).and_then(|request_line| {
+                Ok((request_line, read_headers_into(&mut head, config, scratch)?))
+            });
+        // The budget counts what was consumed even when parsing failed
+        // partway.
+        let head_len = budget - head.limit();
+        if head_len > config.max_request_head_bytes as u64 {
+            return Err(HttpError::RequestHeadTooLarge);
         }
+
+        let (request_line, mut headers) = parsed?;
+        check_body_framing(&mut headers)?;
+        let query = request_line
+            .target
+            .split_once('?')
+            .map_or_else(Vec::new, |(_, query)| parse_query(query));
         Ok(Self {
             request_line,
             headers,
+            query,
             body: None,
+            body_reader: Arc::default(),
+            interim_writer: Arc::default(),
+            deadline: None,
+            chunk_limits: ChunkLimits::new(config),
+            head_len: head_len as usize,
         })
     }
 
-    pub fn get_header(&self, header_name: &str) -> Option<&String> {
+    /// The number of bytes the request line and headers took on the wire,
+    /// line terminators and the blank line ending the head included.
+    ///
+    /// ```
+    /// use std::io::{BufRead, Cursor};
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let raw = "GET / HTTP/1.1\r\nHost: a\r\n\r\nhello";
+    /// let mut stream = Cursor::new(raw);
+    /// let request = HttpRequest::from_reader(&mut stream, &ServerConfig::default())?;
+    /// assert_eq!(request.head_len(), 27);
+    /// assert_eq!(stream.fill_buf()?, b"hello");
+    /// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+    /// ```
+    pub fn head_len(&self) -> usize {
+        self.head_len
+    }
+
+    /// The value of the first header named `header_name`, ignoring case as
+    /// header names are case-insensitive.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let raw = "GET / HTTP/1.1\r\nuser-agent: x\r\n\r\n";
+    /// let request = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())?;
+    /// assert_eq!(request.get_header("User-Agent"), Some("x"));
+    /// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+    /// ```
+    pub fn get_header(&self, header_name: &str) -> Option<&str> {
+        find_header(&self.headers, header_name)
+    }
+
+    /// The `Host` header, which names the authority the request is for.
+    pub fn get_host(&self) -> Option<&str> {
+        self.get_header("Host")
+    }
+
+    /// The `Host` header split into its host name and optional port. IPv6
+    /// literals are returned without their brackets. Returns `None` if there
+    /// is no `Host` header or its port is not a valid number.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let parse = |host: &str| {
+    ///     let raw = format!("GET / HTTP/1.1\r\nHost: {host}\r\n\r\n");
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default()).unwrap()
+    /// };
+    ///
+    /// assert_eq!(parse("127.0.0.1:8443").host_and_port(), Some(("127.0.0.1", Some(8443))));
+    /// assert_eq!(parse("[::1]:8080").host_and_port(), Some(("::1", Some(8080))));
+    /// assert_eq!(parse("[::1]").host_and_port(), Some(("::1", None)));
+    /// assert_eq!(parse("example.com").host_and_port(), Some(("example.com", None)));
+    /// ```
+    pub fn host_and_port(&self) -> Option<(&str, Option<u16>)> {
+        let host = self.get_host()?;
+        let (name, port) = match host.strip_prefix('[') {
+            Some(literal) => {
+                let (name, rest) = literal.split_once(']')?;
+                match rest {
+                    "" => (name, None),
+                    rest => (name, Some(rest.strip_prefix(':')?)),
+                }
+            }
+            None => match host.split_once(':') {
+                Some((name, port)) => (name, Some(port)),
+                None => (host, None),
+            },
+        };
+        match port {
+            Some(port) => Some((name, Some(port.parse().ok()?))),
+            None => Some((name, None)),
+        }
+    }
+
+    /// The request's headers in the order they were received.
+    pub fn get_headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// The options named by the request's `Connection` header, lowercased.
+    /// Besides `close` and `keep-alive` these can name further headers that
+    /// apply only to the current connection.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let raw = "GET / HTTP/1.1\r\nConnection: close, X-Foo\r\n\r\n";
+    /// let request = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())?;
+    /// assert_eq!(request.connection_options(), ["close", "x-foo"]);
+    /// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+    /// ```
+    pub fn connection_options(&self) -> Vec<String> {
+        connection_options(&self.headers)
+    }
+
+    /// The value of the cookie `name` from the `Cookie` headers.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let raw = "GET / HTTP/1.1\r\nCookie: theme=dark; sid=abc\r\nCookie: lang=en\r\n\r\n";
+    /// let request = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())?;
+    /// assert_eq!(request.get_cookie("sid"), Some("abc"));
+    /// assert_eq!(request.get_cookie("lang"), Some("en"));
+    /// assert_eq!(request.get_cookie("SID"), None);
+    /// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+    /// ```
+    pub fn get_cookie(&self, name: &str) -> Option<&str> {
         self.headers
             .iter()
-            .find(|(name, _)| header_name == name)
-            .and_then(|(_, value)| Some(value))
+            .filter(|(header, _)| header.eq_ignore_ascii_case("Cookie"))
+            .flat_map(|(_, value)| value.split(';'))
+            .find_map(|cookie| {
+                let (cookie_name, value) = cookie.trim().split_once('=')?;
+                (cookie_name == name).then_some(value)
+            })
+    }
+
+    /// Whether the `TE` header lists `trailers`, meaning the client accepts
+    /// trailer fields after a chunked body.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let accepts_trailers = |headers: &str| {
+    ///     let raw = format!("GET / HTTP/1.1\r\n{headers}\r\n");
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())
+    ///         .unwrap()
+    ///         .accepts_trailers()
+    /// };
+    ///
+    /// assert!(accepts_trailers("TE: Trailers\r\n"));
+    /// assert!(accepts_trailers("TE: gzip;q=0.5, trailers\r\n"));
+    /// assert!(!accepts_trailers("TE: gzip\r\n"));
+    /// assert!(!accepts_trailers(""));
+    /// ```
+    pub fn accepts_trailers(&self) -> bool {
+        self.headers
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case("TE"))
+            .flat_map(|(_, value)| value.split(','))
+            .any(|coding| {
+                let coding = coding.split(';').next().unwrap_or_default();
+                coding.trim().eq_ignore_ascii_case("trailers")
+            })
     }
 
     pub fn get_path(&self) -> &str {
         &self.request_line.target
     }
 
+    /// The decoded query string pairs, in order and including repeated keys.
+    pub fn query_pairs(&self) -> &[(String, String)] {
+        &self.query
+    }
+
+    /// The first value of the query parameter `key`.
+    pub fn get_query(&self, key: &str) -> Option<&str> {
+        self.query_all(key).into_iter().next()
+    }
+
+    /// Every value of the query parameter `key`, in order, as sent by
+    /// multi-select form fields.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let raw = "GET /search?tag=a&q=rust+http&tag=b%20c HTTP/1.1\r\n\r\n";
+    /// let request = HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())?;
+    /// assert_eq!(request.query_all("tag"), ["a", "b c"]);
+    /// assert_eq!(request.get_query("q"), Some("rust http"));
+    /// assert!(request.query_all("missing").is_empty());
+    /// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+    /// ```
+    pub fn query_all(&self, key: &str) -> Vec<&str> {
+        self.query
+            .iter()
+            .filter(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
     pub fn get_method(&self) -> &HttpRequestMethod {
         &self.request_line.method
     }
@@ -143,4 +765,634 @@ impl HttpRequest {
     pub fn get_http_version(&self) -> &HttpVersion {
         &self.request_line.version
     }
+
+    /// Reads the whole body from `stream`, which must be positioned just
+    /// after the request head, so that [`get_body`](Self::get_body) returns
+    /// it. Both `Content-Length` and chunked bodies are supported. Other
+    /// transfer codings are refused, as is a coding list that does not end
+    /// in `chunked`.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// };
+    ///
+    /// let read_body = |encoding: &str| {
+    ///     let raw = format!(
+    ///         "POST / HTTP/1.1\r\nTransfer-Encoding: {encoding}\r\n\r\n5\r\nhello\r\n0\r\n\r\n"
+    ///     );
+    ///     let mut stream = Cursor::new(raw);
+    ///     let mut request = HttpRequest::from_reader(&mut stream, &ServerConfig::default())?;
+    ///     request.read_body(&mut stream).map(|()| request)
+    /// };
+    ///
+    /// assert_eq!(read_body("chunked")?.get_body(), Some(&b"hello"[..]));
+    /// assert!(matches!(
+    ///     read_body("gzip, chunked"),
+    ///     Err(HttpError::UnsupportedTransferEncoding(_))
+    /// ));
+    /// assert!(matches!(
+    ///     read_body("chunked, gzip"),
+    ///     Err(HttpError::InvalidTransferEncoding(_))
+    /// ));
+    /// # Ok::<(), HttpError>(())
+    /// ```
+    ///
+    /// Exactly the body is read, up to its `Content-Length` or last chunk,
+    /// so a request pipelined behind it on the same connection is left
+    /// buffered in `stream` for the next parse:
+    ///
+    /// ```
+    /// # use std::io::{BufReader, Cursor};
+    /// # use http_server_starter_rust::http::{
+    /// #     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// # };
+    /// let raw = "POST /a HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello\
+    ///            POST /b HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nbye\r\n0\r\n\r\n\
+    ///            GET /c HTTP/1.1\r\n\r\n";
+    /// let mut stream = BufReader::new(Cursor::new(raw));
+    /// let config = ServerConfig::default();
+    ///
+    /// let mut bodies = Vec::new();
+    /// for path in ["/a", "/b", "/c"] {
+    ///     let mut request = HttpRequest::from_reader(&mut stream, &config)?;
+    ///     request.read_body(&mut stream)?;
+    ///     assert_eq!(request.get_path(), path);
+    ///     bodies.push(request.get_body().map(<[u8]>::to_vec));
+    /// }
+    /// assert_eq!(bodies, [Some(b"hello".to_vec()), Some(b"bye".to_vec()), None]);
+    /// # Ok::<(), HttpError>(())
+    /// ```
+    pub fn read_body<R: BufRead>(&mut self, stream: &mut R) -> Result<(), HttpError> {
+        let mut decoder = BodyDecoder::for_headers(&self.headers, self.chunk_limits)?;
+        // `Content-Length: 0` declares an empty body, which is still a body.
+        if decoder.is_done() && self.get_header("Content-Length").is_none() {
+            return Ok(());
+        }
+
+        let mut body = Vec::new();
+        let mut buffer = [0; 8 * 1024];
+        loop {
+            match decoder.read(stream, &mut buffer)? {
+                0 => break,
+                read => body.extend_from_slice(&buffer[..read]),
+            }
+        }
+        self.body = Some(body);
+        Ok(())
+    }
+
+    /// The body length declared by the `Content-Length` header, or `None`
+    /// if there is none.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let content_length = |headers: &str| {
+    ///     let raw = format!("POST / HTTP/1.1\r\n{headers}\r\n");
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())
+    ///         .unwrap()
+    ///         .content_length()
+    /// };
+    ///
+    /// assert_eq!(content_length("Content-Length: 42\r\n").unwrap().ok(), Some(42));
+    /// assert!(content_length("").is_none());
+    /// for malformed in ["+42", "-1", "4 2", "0x10"] {
+    ///     let header = format!("Content-Length: {malformed}\r\n");
+    ///     assert!(content_length(&header).unwrap().is_err());
+    /// }
+    /// ```
+    pub fn content_length(&self) -> Option<Result<usize, HttpError>> {
+        let value = self.get_header("Content-Length")?;
+        Some(parse_content_length(value).and_then(|length| {
+            usize::try_from(length).map_err(|_| HttpError::InvalidContentLength(value.to_string()))
+        }))
+    }
+
+    pub(crate) fn set_deadline(&mut self, deadline: Option<Instant>) {
+        self.deadline = deadline;
+    }
+
+    /// When the server stops waiting for the handler, from the route's
+    /// [`with_timeout`](super::router::Route::with_timeout) or the
+    /// configured [`handler_timeout`](ServerConfig::handler_timeout).
+    /// `None` if the handler may take as long as it likes.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// The time left until the [`deadline`](Self::deadline), which is zero
+    /// once it has passed.
+    ///
+    /// The server cannot stop a handler that overruns its deadline; it
+    /// answers `504` and discards whatever the handler returns later.
+    /// Handlers doing long or repeated work should check this between
+    /// steps and give up early, returning a partial result or an error,
+    /// rather than work on after nobody is waiting for them.
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig,
+    ///     response::{HttpResponse, HttpStatus},
+    ///     server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server
+    ///     .router()
+    ///     .add(None, "/report", |context| {
+    ///         let version = context.request.get_http_version().to_owned();
+    ///         let mut lines = Vec::new();
+    ///         for part in 0..100 {
+    ///             if context.request.time_remaining() == Some(Duration::ZERO) {
+    ///                 return HttpResponse::from_status(version, HttpStatus::GatewayTimeout);
+    ///             }
+    ///             lines.push(format!("part {part}"));
+    ///         }
+    ///         let mut response = HttpResponse::new(version);
+    ///         response.write_text(&lines.join("\n"));
+    ///         response
+    ///     })
+    ///     .with_timeout(Duration::from_secs(2));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn time_remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    pub(crate) fn set_body_reader(&mut self, reader: BodyReader) {
+        *self
+            .body_reader
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(reader);
+    }
+
+    /// Takes the reader for a body that is streamed from the connection
+    /// instead of buffered, as on routes set up with
+    /// [`Route::with_streaming_body`](super::router::Route::with_streaming_body).
+    /// Returns `None` for buffered bodies, which are available from
+    /// [`get_body`](Self::get_body), and once the reader has been taken.
+    ///
+    /// Whatever part of the body the handler leaves unread is discarded by
+    /// closing the connection.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{self, Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// /// Counts the bytes written and remembers the largest single write.
+    /// #[derive(Default)]
+    /// struct Counter {
+    ///     total: u64,
+    ///     largest: usize,
+    /// }
+    ///
+    /// impl Write for Counter {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         self.total += buf.len() as u64;
+    ///         self.largest = self.largest.max(buf.len());
+    ///         Ok(buf.len())
+    ///     }
+    ///
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server
+    ///     .router()
+    ///     .add(None, "/upload", |context| {
+    ///         let mut counter = Counter::default();
+    ///         let mut reader = context.request.body_reader().unwrap();
+    ///         io::copy(&mut reader, &mut counter).unwrap();
+    ///         let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///         response.write_text(&format!("{} {}", counter.total, counter.largest));
+    ///         response
+    ///     })
+    ///     .with_streaming_body();
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// const SIZE: usize = 32 * 1024 * 1024;
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// write!(
+    ///     stream,
+    ///     "POST /upload HTTP/1.1\r\nContent-Length: {SIZE}\r\nConnection: close\r\n\r\n"
+    /// )?;
+    /// let piece = [b'x'; 64 * 1024];
+    /// for _ in 0..SIZE / piece.len() {
+    ///     stream.write_all(&piece)?;
+    /// }
+    /// let mut received = String::new();
+    /// stream.read_to_string(&mut received)?;
+    ///
+    /// let (total, largest) = received.rsplit("\r\n\r\n").next().unwrap().split_once(' ').unwrap();
+    /// assert_eq!(total.parse::<usize>().unwrap(), SIZE);
+    /// // The body arrived in small pieces rather than as one buffer.
+    /// assert!(largest.parse::<usize>().unwrap() <= 64 * 1024);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn body_reader(&self) -> Option<BodyReader> {
+        self.body_reader
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    /// Copies the body into `writer`, such as a file or a hasher, returning
+    /// the number of bytes copied. A streamed body goes straight from the
+    /// connection to `writer`, decoded from chunked transfer coding if the
+    /// client used it, without being buffered whole. A buffered body is
+    /// written out from [`get_body`](Self::get_body).
+    ///
+    /// A streamed body can only be copied once; after that, and for a
+    /// request without a body, nothing is copied.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server
+    ///     .router()
+    ///     .add(None, "/upload", |context| {
+    ///         let mut saved = Vec::new();
+    ///         let copied = context.request.copy_body_to(&mut saved).unwrap();
+    ///         let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///         response.write_text(&format!("{copied} bytes: {}", String::from_utf8_lossy(&saved)));
+    ///         response
+    ///     })
+    ///     .with_streaming_body();
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// stream.write_all(
+    ///     b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n\
+    ///       5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n",
+    /// )?;
+    /// let mut received = String::new();
+    /// stream.read_to_string(&mut received)?;
+    /// assert!(received.ends_with("\r\n\r\n11 bytes: hello world"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn copy_body_to<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<u64> {
+        if let Some(mut reader) = self.body_reader() {
+            return io::copy(&mut reader, writer);
+        }
+        let body = self.get_body().unwrap_or_default();
+        writer.write_all(body)?;
+        Ok(body.len() as u64)
+    }
+
+    /// Lets [`send_early_hints`](Self::send_early_hints) write to the
+    /// connection until [`take_interim_writer`](Self::take_interim_writer).
+    pub(crate) fn set_interim_writer(&mut self, writer: InterimWriter) {
+        *self
+            .interim_writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(writer);
+    }
+
+    pub(crate) fn take_interim_writer(&self) -> Option<InterimWriter> {
+        self.interim_writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+    }
+
+    /// Sends a `103 Early Hints` response with `headers`, typically `Link`
+    /// headers naming assets to preload, while the handler is still working
+    /// on the final response. It can be called more than once.
+    ///
+    /// Hints are advisory, so this does nothing for HTTP/1.0 clients, which
+    /// do not understand interim responses, for requests that did not come
+    /// from a server connection, and once the final response has been sent.
+    ///
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpStream;
+    /// use std::thread;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.get("/", |context| {
+    ///     let _ = context.request.send_early_hints(&[("Link", "</style.css>; rel=preload; as=style")]);
+    ///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///     response.write_text("hello");
+    ///     response
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// stream.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")?;
+    /// let mut response = String::new();
+    /// stream.read_to_string(&mut response)?;
+    ///
+    /// let (hints, last) = response.split_once("\r\n\r\n").unwrap();
+    /// assert_eq!(
+    ///     hints,
+    ///     "HTTP/1.1 103 Early Hints\r\nLink: </style.css>; rel=preload; as=style"
+    /// );
+    /// assert!(last.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// assert!(last.ends_with("\r\n\r\nhello"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn send_early_hints(&self, headers: &[(&str, &str)]) -> io::Result<()> {
+        if matches!(self.request_line.version, HttpVersion::Http1_0) {
+            return Ok(());
+        }
+        let mut writer = self
+            .interim_writer
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let Some(writer) = writer.as_mut() else {
+            return Ok(());
+        };
+
+        let mut response =
+            HttpResponse::from_status(self.request_line.version.to_owned(), HttpStatus::EarlyHints);
+        for (name, value) in headers {
+            response.add_header(name, value);
+        }
+        writer(&response)
+    }
+
+    /// Whether the client is waiting for a `100 Continue` before sending
+    /// the body: it sent `Expect: 100-continue` over HTTP/1.1 with a
+    /// `POST`, `PUT` or `PATCH` that declares a non-empty body. Other
+    /// requests have no body to wait for, so they are answered without
+    /// one.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let expects_continue = |request_line: &str, headers: &str| {
+    ///     let raw = format!("{request_line}\r\nExpect: 100-continue\r\n{headers}\r\n");
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())
+    ///         .unwrap()
+    ///         .expects_continue()
+    /// };
+    ///
+    /// assert!(expects_continue("POST /upload HTTP/1.1", "Content-Length: 5\r\n"));
+    /// assert!(expects_continue("PUT /upload HTTP/1.1", "Transfer-Encoding: chunked\r\n"));
+    /// assert!(!expects_continue("POST /upload HTTP/1.1", "Content-Length: 0\r\n"));
+    /// assert!(!expects_continue("POST /upload HTTP/1.0", "Content-Length: 5\r\n"));
+    /// assert!(!expects_continue("GET / HTTP/1.1", ""));
+    /// assert!(!expects_continue("GET / HTTP/1.1", "Content-Length: 5\r\n"));
+    /// ```
+    ///
+    /// A bodyless `GET` that sends the header anyway is answered straight
+    /// away, without waiting for a body:
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.get("/", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// stream.write_all(b"GET / HTTP/1.1\r\nExpect: 100-continue\r\nConnection: close\r\n\r\n")?;
+    /// let mut received = String::new();
+    /// stream.read_to_string(&mut received)?;
+    /// assert!(received.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn expects_continue(&self) -> bool {
+        let expects = self
+            .get_header("Expect")
+            .is_some_and(|expect| expect.trim().eq_ignore_ascii_case("100-continue"));
+        let carries_body = matches!(
+            self.request_line.method,
+            HttpRequestMethod::Post | HttpRequestMethod::Put | HttpRequestMethod::Patch
+        );
+        let declares_body = self.get_header("Transfer-Encoding").is_some()
+            || self
+                .content_length()
+                .is_some_and(|length| length.is_ok_and(|length| length > 0));
+        expects
+            && carries_body
+            && declares_body
+            && matches!(self.request_line.version, HttpVersion::Http1_1)
+    }
+
+    /// Whether the client asked for the connection to stay open: the default
+    /// for HTTP/1.1 unless it sent `Connection: close`, and opt-in with
+    /// `Connection: keep-alive` for HTTP/1.0.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let wants_keep_alive = |version: &str, connection: &str| {
+    ///     let raw = format!("GET / {version}\r\n{connection}\r\n");
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())
+    ///         .unwrap()
+    ///         .wants_keep_alive()
+    /// };
+    ///
+    /// assert!(wants_keep_alive("HTTP/1.1", ""));
+    /// assert!(!wants_keep_alive("HTTP/1.1", "Connection: close\r\n"));
+    /// assert!(!wants_keep_alive("HTTP/1.0", ""));
+    /// assert!(wants_keep_alive("HTTP/1.0", "Connection: Keep-Alive\r\n"));
+    /// ```
+    pub fn wants_keep_alive(&self) -> bool {
+        let options = self.connection_options();
+        match self.request_line.version {
+            HttpVersion::Http1_0 => options.iter().any(|option| option == "keep-alive"),
+            _ => !options.iter().any(|option| option == "close"),
+        }
+    }
+
+    /// Deserializes the body read by [`read_body`](Self::read_body) into a
+    /// `T`; see [`FromRequestBody`].
+    pub fn parse_body<T: FromRequestBody>(&self) -> Result<T, HttpError> {
+        T::from_body(self)
+    }
+
+    /// The media type of the `Content-Type` header, without parameters
+    /// and in lowercase, such as `application/json`.
+    pub fn media_type(&self) -> Option<String> {
+        let content_type = self.get_header("Content-Type")?;
+        let media_type = content_type.split(';').next().unwrap_or_default();
+        Some(media_type.trim().to_ascii_lowercase())
+    }
+
+    /// Fails with [`HttpError::UnsupportedMediaType`] unless the
+    /// [`media_type`](Self::media_type) is `expected`, for body parsers to
+    /// refuse bodies they cannot read.
+    pub fn require_media_type(&self, expected: &str) -> Result<(), HttpError> {
+        match self.media_type() {
+            Some(media_type) if media_type.eq_ignore_ascii_case(expected) => Ok(()),
+            media_type => Err(HttpError::UnsupportedMediaType(
+                media_type.unwrap_or_default(),
+            )),
+        }
+    }
+
+    /// The `charset` parameter of the `Content-Type` header, in lowercase
+    /// and without quotes.
+    pub fn charset(&self) -> Option<String> {
+        let content_type = self.get_header("Content-Type")?;
+        content_type.split(';').skip(1).find_map(|param| {
+            let (name, value) = param.split_once('=')?;
+            name.trim().eq_ignore_ascii_case("charset").then(|| {
+                let value = value.trim();
+                let value = value.strip_prefix('"').unwrap_or(value);
+                let value = value.strip_suffix('"').unwrap_or(value);
+                value.to_ascii_lowercase()
+            })
+        })
+    }
+
+    /// The body read by [`read_body`](Self::read_body) decoded as text by
+    /// the charset of its `Content-Type`, which is UTF-8 when none is
+    /// given. UTF-8 and ISO-8859-1 are supported; other charsets fail with
+    /// [`HttpError::UnsupportedCharset`], and invalid UTF-8 with
+    /// [`HttpError::Utf8`]. A missing body is empty text.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// };
+    ///
+    /// let text = |content_type: &str, body: &[u8]| -> Result<String, HttpError> {
+    ///     let mut raw = format!(
+    ///         "POST / HTTP/1.1\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+    ///         body.len()
+    ///     )
+    ///     .into_bytes();
+    ///     raw.extend_from_slice(body);
+    ///     let mut stream = Cursor::new(raw);
+    ///     let mut request = HttpRequest::from_reader(&mut stream, &ServerConfig::default())?;
+    ///     request.read_body(&mut stream)?;
+    ///     request.text().map(String::from)
+    /// };
+    ///
+    /// assert_eq!(text("text/plain", "café".as_bytes())?, "café");
+    /// assert_eq!(text("text/plain; charset=\"UTF-8\"", "café".as_bytes())?, "café");
+    /// assert_eq!(text("text/plain; charset=iso-8859-1", b"caf\xe9")?, "café");
+    /// assert!(matches!(text("text/plain", b"caf\xe9"), Err(HttpError::Utf8(_))));
+    /// assert!(matches!(
+    ///     text("text/plain; charset=shift_jis", b"abc"),
+    ///     Err(HttpError::UnsupportedCharset(_))
+    /// ));
+    /// # Ok::<(), HttpError>(())
+    /// ```
+    pub fn text(&self) -> Result<Cow<'_, str>, HttpError> {
+        let body = self.get_body().unwrap_or_default();
+        match self.charset().as_deref() {
+            None | Some("utf-8" | "utf8" | "us-ascii") => match std::str::from_utf8(body) {
+                Ok(text) => Ok(Cow::Borrowed(text)),
+                // `HttpError::Utf8` wraps a `FromUtf8Error`, which owns the bytes.
+                Err(_) => Err(String::from_utf8(body.to_vec()).unwrap_err().into()),
+            },
+            // Latin-1 code points are the Unicode ones with the same value.
+            Some("iso-8859-1" | "iso_8859-1" | "latin1" | "latin-1" | "l1") => {
+                Ok(Cow::Owned(body.iter().copied().map(char::from).collect()))
+            }
+            Some(charset) => Err(HttpError::UnsupportedCharset(charset.to_string())),
+        }
+    }
+
+    /// The language from `available` that the `Accept-Language` header
+    /// weights highest, or the first one if the header is absent.
+    ///
+    /// A language range matches the same tag and any more specific one, so
+    /// `en` matches `en-US`, and the most specific range matching a tag
+    /// gives its weight. `*` matches every tag, and a weight of `0` rules a
+    /// tag out. Ties go to the language listed first in `available`;
+    /// `None` means none is acceptable.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, request::HttpRequest};
+    ///
+    /// let available = ["en-US", "fr", "de"];
+    /// let preferred = |headers: &str| {
+    ///     let raw = format!("GET / HTTP/1.1\r\n{headers}\r\n");
+    ///     HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default())
+    ///         .unwrap()
+    ///         .preferred_language(&available)
+    /// };
+    ///
+    /// assert_eq!(preferred(""), Some("en-US"));
+    /// assert_eq!(preferred("Accept-Language: de, fr;q=0.9\r\n"), Some("de"));
+    /// assert_eq!(preferred("Accept-Language: fr;q=0.5, en;q=0.8\r\n"), Some("en-US"));
+    /// assert_eq!(preferred("Accept-Language: *;q=0.1, fr;q=0.5\r\n"), Some("fr"));
+    /// assert_eq!(preferred("Accept-Language: en;q=0, *\r\n"), Some("fr"));
+    /// assert_eq!(preferred("Accept-Language: ja, zh\r\n"), None);
+    /// ```
+    pub fn preferred_language<'a>(&self, available: &[&'a str]) -> Option<&'a str> {
+        negotiation::preferred_language(self.get_header("Accept-Language"), available)
+    }
+
+    /// The body read by [`read_body`](Self::read_body). An empty body sent
+    /// with `Content-Length: 0` is `Some`, while a request without a body
+    /// is `None`.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, errors::HttpError, request::HttpRequest,
+    /// };
+    ///
+    /// let read_body = |headers: &str| {
+    ///     let mut stream = Cursor::new(format!("POST / HTTP/1.1\r\n{headers}\r\n"));
+    ///     let mut request = HttpRequest::from_reader(&mut stream, &ServerConfig::default())?;
+    ///     request.read_body(&mut stream).map(|()| request)
+    /// };
+    ///
+    /// assert_eq!(read_body("Content-Length: 0\r\n")?.get_body(), Some(&b""[..]));
+    /// assert_eq!(read_body("")?.get_body(), None);
+    /// # Ok::<(), HttpError>(())
+    /// ```
+    pub fn get_body(&self) -> Option<&[u8]> {
+        self.body.as_deref()
+    }
 }
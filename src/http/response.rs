@@ -1,81 +1,1295 @@
-use super::HttpVersion;
+use std::{
+    borrow::Cow,
+    fmt,
+    fs::File,
+    io::{self, BufRead, Read, Write},
+    str::FromStr,
+};
+
+use super::{
+    conditional::revalidate,
+    config::ServerConfig,
+    connection::Connection,
+    errors::HttpError,
+    request::{read_line, HttpRequest},
+    sha256::content_tag,
+    writer::{BufferedBodyWriter, ChunkedWriter},
+    HttpVersion,
+};
 
 pub enum HttpStatus {
+    Continue,
+    SwitchingProtocols,
+    EarlyHints,
     Ok,
+    Created,
+    NoContent,
+    PartialContent,
+    MovedPermanently,
+    Found,
+    SeeOther,
+    NotModified,
+    TemporaryRedirect,
+    PermanentRedirect,
+    BadRequest,
+    Forbidden,
     NotFound,
+    PreconditionFailed,
+    UnsupportedMediaType,
+    RangeNotSatisfiable,
+    RequestHeaderFieldsTooLarge,
+    InternalServerError,
+    NotImplemented,
+    BadGateway,
+    ServiceUnavailable,
+    GatewayTimeout,
+    HttpVersionNotSupported,
 }
 
-struct HttpResponseStatusLine {
+impl HttpStatus {
+    pub fn code(&self) -> u16 {
+        match self {
+            HttpStatus::Continue => 100,
+            HttpStatus::SwitchingProtocols => 101,
+            HttpStatus::EarlyHints => 103,
+            HttpStatus::Ok => 200,
+            HttpStatus::Created => 201,
+            HttpStatus::NoContent => 204,
+            HttpStatus::PartialContent => 206,
+            HttpStatus::MovedPermanently => 301,
+            HttpStatus::Found => 302,
+            HttpStatus::SeeOther => 303,
+            HttpStatus::NotModified => 304,
+            HttpStatus::TemporaryRedirect => 307,
+            HttpStatus::PermanentRedirect => 308,
+            HttpStatus::BadRequest => 400,
+            HttpStatus::Forbidden => 403,
+            HttpStatus::NotFound => 404,
+            HttpStatus::PreconditionFailed => 412,
+            HttpStatus::UnsupportedMediaType => 415,
+            HttpStatus::RangeNotSatisfiable => 416,
+            HttpStatus::RequestHeaderFieldsTooLarge => 431,
+            HttpStatus::InternalServerError => 500,
+            HttpStatus::NotImplemented => 501,
+            HttpStatus::BadGateway => 502,
+            HttpStatus::ServiceUnavailable => 503,
+            HttpStatus::GatewayTimeout => 504,
+            HttpStatus::HttpVersionNotSupported => 505,
+        }
+    }
+
+    pub fn reason(&self) -> &'static str {
+        match self {
+            HttpStatus::Continue => "Continue",
+            HttpStatus::SwitchingProtocols => "Switching Protocols",
+            HttpStatus::EarlyHints => "Early Hints",
+            HttpStatus::Ok => "OK",
+            HttpStatus::Created => "Created",
+            HttpStatus::NoContent => "No Content",
+            HttpStatus::PartialContent => "Partial Content",
+            HttpStatus::MovedPermanently => "Moved Permanently",
+            HttpStatus::Found => "Found",
+            HttpStatus::SeeOther => "See Other",
+            HttpStatus::NotModified => "Not Modified",
+            HttpStatus::TemporaryRedirect => "Temporary Redirect",
+            HttpStatus::PermanentRedirect => "Permanent Redirect",
+            HttpStatus::BadRequest => "Bad Request",
+            HttpStatus::Forbidden => "Forbidden",
+            HttpStatus::NotFound => "Not Found",
+            HttpStatus::PreconditionFailed => "Precondition Failed",
+            HttpStatus::UnsupportedMediaType => "Unsupported Media Type",
+            HttpStatus::RangeNotSatisfiable => "Range Not Satisfiable",
+            HttpStatus::RequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
+            HttpStatus::InternalServerError => "Internal Server Error",
+            HttpStatus::NotImplemented => "Not Implemented",
+            HttpStatus::BadGateway => "Bad Gateway",
+            HttpStatus::ServiceUnavailable => "Service Unavailable",
+            HttpStatus::GatewayTimeout => "Gateway Timeout",
+            HttpStatus::HttpVersionNotSupported => "HTTP Version Not Supported",
+        }
+    }
+}
+
+pub struct HttpResponseStatusLine {
     version: HttpVersion,
-    status: HttpStatus,
+    status_code: u16,
+    reason: Cow<'static, str>,
+    /// Whether the reason phrase is left out when the line is written.
+    omit_reason: bool,
 }
 
 impl HttpResponseStatusLine {
     fn new(version: HttpVersion, status: HttpStatus) -> Self {
-        Self { version, status }
+        Self {
+            version,
+            status_code: status.code(),
+            reason: Cow::Borrowed(status.reason()),
+            omit_reason: false,
+        }
     }
 
-    fn to_string(&self) -> String {
-        match self.version {
-            HttpVersion::Http1_0 => "HTTP/1.0",
-            HttpVersion::Http1_1 => "HTTP/1.1",
-            HttpVersion::Http2_0 => "HTTP/2.0",
+    pub fn from_stream<R: BufRead>(stream: &mut R) -> Result<Self, HttpError> {
+        let line =
+            String::from_utf8(read_line(stream, &ServerConfig::default())?).map_err(|err| {
+                HttpError::MalformedStatusLine(String::from_utf8_lossy(err.as_bytes()).into_owned())
+            })?;
+        line.parse()
+    }
+
+    fn set_status(&mut self, status: HttpStatus) {
+        self.status_code = status.code();
+        self.reason = Cow::Borrowed(status.reason());
+    }
+
+    pub fn get_http_version(&self) -> &HttpVersion {
+        &self.version
+    }
+
+    pub fn get_status_code(&self) -> u16 {
+        self.status_code
+    }
+
+    pub fn get_reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl FromStr for HttpResponseStatusLine {
+    type Err = HttpError;
+
+    /// Parses a status line such as `HTTP/1.1 404 Not Found`. The reason
+    /// phrase is everything after the status code and may contain spaces.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponseStatusLine, HttpVersion};
+    ///
+    /// for line in ["HTTP/1.1 404 Not Found", "HTTP/1.0 599 Custom Reason", "HTTP/1.1 204 "] {
+    ///     let status_line: HttpResponseStatusLine = line.parse()?;
+    ///     assert_eq!(status_line.to_string(), line);
+    /// }
+    ///
+    /// let status_line: HttpResponseStatusLine = "HTTP/1.0 599 Custom Reason".parse()?;
+    /// assert_eq!(*status_line.get_http_version(), HttpVersion::Http1_0);
+    /// assert_eq!(status_line.get_status_code(), 599);
+    /// assert_eq!(status_line.get_reason(), "Custom Reason");
+    /// assert!("HTTP/1.1 20 OK".parse::<HttpResponseStatusLine>().is_err());
+    /// # Ok::<(), http_server_starter_rust::http::errors::HttpError>(())
+    /// ```
+    fn from_str(line: &str) -> Result<Self, HttpError> {
+        let malformed = || HttpError::MalformedStatusLine(line.to_string());
+
+        let mut parts = line.splitn(3, ' ');
+        let (Some(version), Some(status_code)) = (parts.next(), parts.next()) else {
+            return Err(malformed());
+        };
+        let version = version.parse::<HttpVersion>()?;
+        if status_code.len() != 3 || !status_code.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(malformed());
         }
-        .to_string()
-            + " "
-            + match self.status {
-                HttpStatus::Ok => "200 OK",
-                HttpStatus::NotFound => "404 Not Found",
-            }
+        let status_code = status_code.parse().map_err(|_| malformed())?;
+        let reason = parts.next().unwrap_or_default().to_string();
+
+        Ok(Self {
+            version,
+            status_code,
+            reason: Cow::Owned(reason),
+            omit_reason: false,
+        })
+    }
+}
+
+impl fmt::Display for HttpResponseStatusLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = if self.omit_reason { "" } else { &self.reason };
+        write!(
+            f,
+            "{} {} {}",
+            self.version.as_str(),
+            self.status_code,
+            reason
+        )
     }
 }
 
+/// An HTTP response under construction.
+///
+/// Besides the `write_*` helpers, the body can be built incrementally with
+/// `write!`, since the response implements [`std::fmt::Write`]. The
+/// `Content-Length` header is computed from the final body when the response
+/// is serialized, unless a handler has set one explicitly.
+///
+/// ```
+/// use std::fmt::Write;
+///
+/// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+///
+/// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+/// response.add_header("Content-Type", "text/plain");
+/// for n in 1..=3 {
+///     write!(response, "count: {}\n", n).unwrap();
+/// }
+///
+/// assert_eq!(
+///     response.to_bytes(),
+///     b"HTTP/1.1 200 OK\r\n\
+///      Content-Type: text/plain\r\n\
+///      Content-Length: 27\r\n\
+///      \r\n\
+///      count: 1\ncount: 2\ncount: 3\n"
+/// );
+/// ```
 pub struct HttpResponse {
     status_line: HttpResponseStatusLine,
     headers: Vec<(String, String)>,
-    content: String,
+    content: Vec<u8>,
+    stream: Option<StreamingBody>,
+    trailers_accepted: bool,
+    auto_etag: bool,
+    /// Whether a body of unknown length is sent without framing, ended by
+    /// closing the connection.
+    close_delimited: bool,
 }
 
+/// A body written straight to the connection instead of being buffered.
+enum StreamingBody {
+    Chunked(ChunkedBody),
+    Sized { length: u64, body: SizedBody },
+    Buffered { threshold: usize, body: SizedBody },
+    File { file: File, length: u64 },
+}
+
+type ChunkedBody = Box<dyn FnOnce(&mut ChunkedWriter<'_>) -> io::Result<()> + Send>;
+type SizedBody = Box<dyn FnOnce(&mut dyn Write) -> io::Result<()> + Send>;
+
 impl HttpResponse {
     pub fn new(version: HttpVersion) -> Self {
         Self {
             status_line: HttpResponseStatusLine::new(version, HttpStatus::Ok),
-            content: "".to_owned(),
+            content: Vec::new(),
             headers: Vec::new(),
+            stream: None,
+            trailers_accepted: false,
+            auto_etag: false,
+            close_delimited: false,
         }
     }
 
+    /// An empty response with `status`.
+    pub fn from_status(version: HttpVersion, status: HttpStatus) -> Self {
+        let mut response = Self::new(version);
+        response.set_status(status);
+        response
+    }
+
+    /// An empty `204 No Content` response, for handlers that succeed without
+    /// anything to return.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// assert_eq!(
+    ///     HttpResponse::no_content(HttpVersion::Http1_1).to_bytes(),
+    ///     b"HTTP/1.1 204 No Content\r\n\r\n"
+    /// );
+    /// ```
+    pub fn no_content(version: HttpVersion) -> Self {
+        Self::from_status(version, HttpStatus::NoContent)
+    }
+
+    /// A complete response with `status`, declaring `body` as
+    /// `content_type`.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{
+    ///     response::{HttpResponse, HttpStatus},
+    ///     HttpVersion,
+    /// };
+    ///
+    /// let response = HttpResponse::with_body(
+    ///     HttpVersion::Http1_1,
+    ///     HttpStatus::NotFound,
+    ///     "text/plain",
+    ///     "no such user",
+    /// );
+    /// assert_eq!(
+    ///     response.to_bytes(),
+    ///     b"HTTP/1.1 404 Not Found\r\n\
+    ///      Content-Type: text/plain\r\n\
+    ///      Content-Length: 12\r\n\
+    ///      \r\n\
+    ///      no such user"
+    /// );
+    /// ```
+    pub fn with_body(
+        version: HttpVersion,
+        status: HttpStatus,
+        content_type: &str,
+        body: impl AsRef<[u8]>,
+    ) -> Self {
+        let mut response = Self::from_status(version, status);
+        response
+            .add_header("Content-Type", content_type)
+            .append_bytes(body.as_ref());
+        response
+    }
+
+    /// A `200 OK` response with `body`; see [`with_body`](Self::with_body).
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let response = HttpResponse::ok(HttpVersion::Http1_1, "application/json", "[]");
+    /// assert_eq!(response.get_status_code(), 200);
+    /// assert_eq!(response.get_header("Content-Type"), Some("application/json"));
+    /// assert!(response.to_bytes().ends_with(b"Content-Length: 2\r\n\r\n[]"));
+    /// ```
+    pub fn ok(version: HttpVersion, content_type: &str, body: impl AsRef<[u8]>) -> Self {
+        Self::with_body(version, HttpStatus::Ok, content_type, body)
+    }
+
+    /// An empty response with the status for `error`, such as `415` for a
+    /// body [`parse_body`](super::request::HttpRequest::parse_body) could
+    /// not read. The server fills it in from
+    /// [`ServerConfig::error_pages`] when a page is configured for the
+    /// status.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::{ErrorPage, ServerConfig},
+    ///     errors::HttpError,
+    ///     request::HttpRequest,
+    ///     response::HttpResponse,
+    ///     server::Server,
+    /// };
+    ///
+    /// fn greet(request: &HttpRequest) -> Result<HttpResponse, HttpError> {
+    ///     let form: Vec<(String, String)> = request.parse_body()?;
+    ///     let name = form.iter().find(|(key, _)| key == "name").map_or("?", |(_, value)| value);
+    ///     Ok(HttpResponse::ok(request.get_http_version().to_owned(), "text/plain", format!("hi {name}")))
+    /// }
+    ///
+    /// let mut config = ServerConfig::default();
+    /// config.error_pages.insert(415, ErrorPage::text("send a form"));
+    /// let mut server = Server::bind("127.0.0.1:0", config)?;
+    /// server.post("/greet", |context| {
+    ///     greet(context.request).unwrap_or_else(|e| {
+    ///         HttpResponse::from_error(context.request.get_http_version().to_owned(), &e)
+    ///     })
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let post = |content_type: &str, body: &str| -> std::io::Result<String> {
+    ///     let mut stream = TcpStream::connect(addr)?;
+    ///     write!(
+    ///         stream,
+    ///         "POST /greet HTTP/1.1\r\nContent-Type: {content_type}\r\n\
+    ///          Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+    ///         body.len()
+    ///     )?;
+    ///     let mut received = String::new();
+    ///     stream.read_to_string(&mut received)?;
+    ///     Ok(received)
+    /// };
+    ///
+    /// assert!(post("application/x-www-form-urlencoded", "name=ada")?.ends_with("\r\n\r\nhi ada"));
+    /// let refused = post("application/json", r#"{"name":"ada"}"#)?;
+    /// assert!(refused.starts_with("HTTP/1.1 415 Unsupported Media Type\r\n"));
+    /// assert!(refused.ends_with("\r\n\r\nsend a form"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn from_error(version: HttpVersion, error: &HttpError) -> Self {
+        Self::from_status(version, error.status())
+    }
+
+    /// A `201 Created` response with `body`, such as a representation of
+    /// the created resource; see [`with_body`](Self::with_body).
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let response = HttpResponse::created(HttpVersion::Http1_1, "text/plain", b"made");
+    /// assert!(response.to_bytes().starts_with(b"HTTP/1.1 201 Created\r\n"));
+    /// ```
+    pub fn created(version: HttpVersion, content_type: &str, body: impl AsRef<[u8]>) -> Self {
+        Self::with_body(version, HttpStatus::Created, content_type, body)
+    }
+
+    /// Appends a header. Headers are sent in the order they were added,
+    /// duplicates included, followed by the framing header the response
+    /// adds itself: `Transfer-Encoding: chunked` for a streaming body,
+    /// otherwise `Content-Length` unless one was added explicitly.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    /// response
+    ///     .add_header("X-B", "1")
+    ///     .add_header("X-A", "2")
+    ///     .add_header("X-B", "3");
+    ///
+    /// assert_eq!(
+    ///     response.to_bytes(),
+    ///     b"HTTP/1.1 200 OK\r\n\
+    ///      X-B: 1\r\n\
+    ///      X-A: 2\r\n\
+    ///      X-B: 3\r\n\
+    ///      Content-Length: 0\r\n\
+    ///      \r\n"
+    /// );
+    /// ```
+    ///
+    /// Any `\r` or `\n` in the name or value is stripped rather than
+    /// refused, so that a value built from user input cannot end the header
+    /// early and smuggle in headers of its own or split the response. This
+    /// also covers headers added on the caller's behalf, such as the
+    /// `Location` of a [`redirect`](super::redirect::redirect).
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    /// response.add_header("X-Name", "evil\r\nSet-Cookie: session=stolen");
+    ///
+    /// assert_eq!(
+    ///     response.get_header("X-Name"),
+    ///     Some("evilSet-Cookie: session=stolen")
+    /// );
+    /// assert!(response.get_header("Set-Cookie").is_none());
+    /// ```
     pub fn add_header(&mut self, header_name: &str, header_value: &str) -> &mut Self {
-        self.headers
-            .push((header_name.to_string(), header_value.to_string()));
+        let strip = |text: &str| text.replace(['\r', '\n'], "");
+        self.headers.push((strip(header_name), strip(header_value)));
         self
     }
 
     pub fn set_status(&mut self, status: HttpStatus) -> &mut Self {
-        self.status_line.status = status;
+        self.status_line.set_status(status);
         self
     }
 
+    /// Leaves the reason phrase out of the status line, as minimal clients
+    /// and HTTP/2 do without it. The space after the status code stays,
+    /// since HTTP/1.1 requires it. [`get_reason`](Self::get_reason) still
+    /// returns the phrase.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{
+    ///     response::{HttpResponse, HttpStatus},
+    ///     HttpVersion,
+    /// };
+    ///
+    /// let mut response = HttpResponse::from_status(HttpVersion::Http1_1, HttpStatus::NotFound);
+    /// assert!(response.to_bytes().starts_with(b"HTTP/1.1 404 Not Found\r\n"));
+    /// response.omit_reason_phrase().set_status(HttpStatus::Ok);
+    /// assert!(response.to_bytes().starts_with(b"HTTP/1.1 200 \r\n"));
+    /// assert_eq!(response.get_reason(), "OK");
+    /// ```
+    pub fn omit_reason_phrase(&mut self) -> &mut Self {
+        self.status_line.omit_reason = true;
+        self
+    }
+
+    /// Has the server tag a `200 OK` with a buffered body with a weak
+    /// `ETag` hashed from the body, and answer a `GET` or `HEAD` whose
+    /// `If-None-Match` already has that tag with `304 Not Modified`. This
+    /// brings revalidation to generated responses, though the handler
+    /// still runs to produce the body being compared. Responses that set
+    /// their own `ETag` or stream their body are left alone.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.get("/report", |context| {
+    ///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///     response.write_json("{\"total\":42}").auto_etag();
+    ///     response
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let get = |headers: &str| -> std::io::Result<String> {
+    ///     let mut stream = TcpStream::connect(addr)?;
+    ///     write!(stream, "GET /report HTTP/1.1\r\n{headers}Connection: close\r\n\r\n")?;
+    ///     let mut received = String::new();
+    ///     stream.read_to_string(&mut received)?;
+    ///     Ok(received)
+    /// };
+    /// let first = get("")?;
+    /// assert!(first.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// let etag = first
+    ///     .lines()
+    ///     .find_map(|line| line.strip_prefix("ETag: "))
+    ///     .unwrap();
+    /// // The body's length in hex, then the first 8 bytes of its SHA-256.
+    /// assert_eq!(etag, "W/\"c-bb1453c7fec8dfc7\"");
+    ///
+    /// let second = get(&format!("If-None-Match: {etag}\r\n"))?;
+    /// assert!(second.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+    /// assert!(second.contains(&format!("\r\nETag: {etag}\r\n")));
+    /// assert!(!second.contains("Content-Length"));
+    /// assert!(second.ends_with("\r\n\r\n"));
+    ///
+    /// let stale = get("If-None-Match: W/\"0-0\"\r\n")?;
+    /// assert!(stale.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn auto_etag(&mut self) -> &mut Self {
+        self.auto_etag = true;
+        self
+    }
+
+    /// Tags the response and turns it into a `304 Not Modified` for
+    /// `request` if it asked for [`auto_etag`](Self::auto_etag).
+    pub(crate) fn apply_auto_etag(&mut self, request: &HttpRequest) {
+        if !self.auto_etag
+            || self.get_status_code() != HttpStatus::Ok.code()
+            || self.stream.is_some()
+            || self.has_header("ETag")
+        {
+            return;
+        }
+        let etag = format!("W/\"{}\"", content_tag(&self.content));
+        self.add_header("ETag", &etag);
+        revalidate(request, self);
+    }
+
+    /// Turns the response into a `304 Not Modified`, dropping the body but
+    /// keeping the validators and other headers.
+    pub(crate) fn make_not_modified(&mut self) {
+        self.set_status(HttpStatus::NotModified)
+            .remove_header("Content-Length");
+        self.content.clear();
+        self.stream = None;
+    }
+
+    pub fn get_status_code(&self) -> u16 {
+        self.status_line.get_status_code()
+    }
+
+    pub fn get_reason(&self) -> &str {
+        self.status_line.get_reason()
+    }
+
+    /// Sends a streaming body of unknown length without chunked coding,
+    /// for an HTTP/1.0 client, which marks the end of the body by the
+    /// connection closing. Returns whether the response has such a body,
+    /// in which case the connection must be closed after it.
+    pub(crate) fn delimit_by_close(&mut self) -> bool {
+        self.close_delimited = self.get_body_length().is_none();
+        self.close_delimited
+    }
+
+    /// The number of body bytes the response will send, or `None` for a
+    /// streaming body whose length is not known up front.
+    pub fn get_body_length(&self) -> Option<u64> {
+        match &self.stream {
+            Some(StreamingBody::Chunked(_) | StreamingBody::Buffered { .. }) => None,
+            Some(StreamingBody::Sized { length, .. } | StreamingBody::File { length, .. }) => {
+                Some(*length)
+            }
+            None => Some(self.content.len() as u64),
+        }
+    }
+
+    /// Whether a body has been set, either buffered or streaming.
+    pub fn has_body(&self) -> bool {
+        !self.content.is_empty() || self.stream.is_some()
+    }
+
     pub fn add_content(&mut self, content: &str) -> &mut Self {
-        self.content = content.to_string();
+        self.content = content.as_bytes().to_vec();
+        self
+    }
+
+    /// Adds `content` to the end of the body. `Content-Length` counts every
+    /// fragment once the response is serialized.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    /// response.add_content("<ul>");
+    /// for item in ["a", "bb", "ccc"] {
+    ///     response.append_content("<li>").append_content(item);
+    /// }
+    /// response.append_bytes(b"</ul>");
+    ///
+    /// let bytes = String::from_utf8(response.to_bytes()).unwrap();
+    /// assert!(bytes.contains("Content-Length: 27\r\n"));
+    /// assert!(bytes.ends_with("\r\n\r\n<ul><li>a<li>bb<li>ccc</ul>"));
+    /// ```
+    pub fn append_content(&mut self, content: &str) -> &mut Self {
+        self.append_bytes(content.as_bytes())
+    }
+
+    pub fn append_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.content.extend_from_slice(bytes);
         self
     }
 
+    /// Sets the `Content-Type` header, replacing any set before, and
+    /// leaves the body and other headers as they are.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    /// response
+    ///     .add_content("<svg/>")
+    ///     .set_content_type("text/plain")
+    ///     .set_content_type("image/svg+xml");
+    /// let sent = String::from_utf8(response.to_bytes()).unwrap();
+    /// assert_eq!(sent.matches("Content-Type").count(), 1);
+    /// assert!(sent.contains("\r\nContent-Type: image/svg+xml\r\n"));
+    /// assert!(sent.ends_with("\r\n\r\n<svg/>"));
+    /// ```
+    pub fn set_content_type(&mut self, mime: &str) -> &mut Self {
+        self.remove_header("Content-Type")
+            .add_header("Content-Type", mime)
+    }
+
+    /// The `Content-Type` the response declares, if any, for deciding
+    /// things such as whether its body is worth compressing.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    /// assert_eq!(response.get_content_type(), None);
+    /// response.write_text("hello");
+    /// assert!(response.get_content_type().unwrap().starts_with("text/plain"));
+    /// response.set_content_type("image/png");
+    /// assert_eq!(response.get_content_type(), Some("image/png"));
+    /// ```
+    pub fn get_content_type(&self) -> Option<&str> {
+        self.get_header("Content-Type")
+    }
+
     pub fn write_text(&mut self, text: &str) -> &mut Self {
-        self.add_header("Content-Type", "text/plain")
-            .add_header("Content-Length", &text.len().to_string())
-            .add_content(text)
+        self.set_content_type("text/plain").add_content(text)
     }
 
-    pub fn to_string(&self) -> String {
-        let mut response = self.status_line.to_string() + "\r\n";
+    /// Sets an HTML document as the body, declared as UTF-8.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    /// response.write_html("<p>café</p>");
+    /// let sent = String::from_utf8(response.to_bytes()).unwrap();
+    /// assert!(sent.contains("\r\nContent-Type: text/html; charset=utf-8\r\n"));
+    /// assert!(sent.contains("\r\nContent-Length: 12\r\n"));
+    /// assert!(sent.ends_with("\r\n\r\n<p>café</p>"));
+    /// ```
+    pub fn write_html(&mut self, html: &str) -> &mut Self {
+        self.set_content_type("text/html; charset=utf-8")
+            .add_content(html)
+    }
 
-        for (name, value) in &self.headers {
-            response += &format!("{name}: {value}\r\n",)
+    /// Sets an already serialized JSON document as the body.
+    pub fn write_json(&mut self, json: &str) -> &mut Self {
+        self.set_content_type("application/json").add_content(json)
+    }
+
+    /// Sets `Content-Disposition: attachment`, so browsers save the body as
+    /// `filename` instead of displaying it. A name with characters outside
+    /// printable ASCII is also sent percent-encoded as an RFC 5987
+    /// `filename*`, next to an ASCII fallback for older clients.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let disposition = |filename: &str| {
+    ///     let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    ///     response.set_attachment(filename);
+    ///     response.get_header("Content-Disposition").unwrap().to_string()
+    /// };
+    ///
+    /// assert_eq!(disposition("report.pdf"), "attachment; filename=\"report.pdf\"");
+    /// assert_eq!(
+    ///     disposition("say \"hi\".txt"),
+    ///     "attachment; filename=\"say \\\"hi\\\".txt\""
+    /// );
+    /// assert_eq!(
+    ///     disposition("résumé.pdf"),
+    ///     "attachment; filename=\"r_sum_.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"
+    /// );
+    /// ```
+    pub fn set_attachment(&mut self, filename: &str) -> &mut Self {
+        let is_plain = |c: char| c == ' ' || c.is_ascii_graphic();
+        let mut fallback = String::with_capacity(filename.len());
+        for c in filename.chars() {
+            match c {
+                '"' | '\\' => {
+                    fallback.push('\\');
+                    fallback.push(c);
+                }
+                c if is_plain(c) => fallback.push(c),
+                _ => fallback.push('_'),
+            }
+        }
+        let mut disposition = format!("attachment; filename=\"{fallback}\"");
+        if !filename.chars().all(is_plain) {
+            disposition += "; filename*=UTF-8''";
+            for byte in filename.bytes() {
+                if byte.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&byte) {
+                    disposition.push(char::from(byte));
+                } else {
+                    disposition += &format!("%{byte:02X}");
+                }
+            }
         }
+        self.remove_header("Content-Disposition")
+            .add_header("Content-Disposition", &disposition)
+    }
+
+    /// Streams the body with chunked transfer coding instead of sending the
+    /// buffered content. `body` runs once the headers have been written and
+    /// flushed.
+    ///
+    /// HTTP/1.0 clients get the body without chunked coding or a
+    /// `Content-Length`, and the connection is closed to end it.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.get("/feed", |context| {
+    ///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///     response.stream_chunked(|writer| {
+    ///         writer.send_chunk(b"first ")?;
+    ///         writer.send_chunk(b"second")
+    ///     });
+    ///     response
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let get = |version: &str| -> std::io::Result<String> {
+    ///     let mut stream = TcpStream::connect(addr)?;
+    ///     write!(stream, "GET /feed {version}\r\nConnection: keep-alive\r\n\r\n")?;
+    ///     // Ends at the 1.0 response's body; the 1.1 connection is kept
+    ///     // alive, so only its head and first chunks are awaited.
+    ///     let mut received = String::new();
+    ///     if version == "HTTP/1.0" {
+    ///         stream.read_to_string(&mut received)?;
+    ///     } else {
+    ///         let mut buffer = [0; 1024];
+    ///         while !received.ends_with("0\r\n\r\n") {
+    ///             let read = stream.read(&mut buffer)?;
+    ///             received += std::str::from_utf8(&buffer[..read]).unwrap();
+    ///         }
+    ///     }
+    ///     Ok(received)
+    /// };
+    ///
+    /// let old = get("HTTP/1.0")?;
+    /// assert!(old.starts_with("HTTP/1.0 200 OK\r\n"));
+    /// assert!(old.contains("\r\nConnection: close\r\n"));
+    /// assert!(!old.contains("Transfer-Encoding") && !old.contains("Content-Length"));
+    /// assert!(old.ends_with("\r\n\r\nfirst second"));
+    ///
+    /// let new = get("HTTP/1.1")?;
+    /// assert!(new.contains("\r\nTransfer-Encoding: chunked\r\n"));
+    /// assert!(new.ends_with("\r\n\r\n6\r\nfirst \r\n6\r\nsecond\r\n0\r\n\r\n"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn stream_chunked<F>(&mut self, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut ChunkedWriter<'_>) -> io::Result<()> + Send + 'static,
+    {
+        self.stream = Some(StreamingBody::Chunked(Box::new(body)));
+        self
+    }
+
+    /// Lets a chunked body send the trailers it adds, for a client that
+    /// accepts them. The server sets this from the request's `TE` header;
+    /// see [`HttpRequest::accepts_trailers`](super::request::HttpRequest::accepts_trailers).
+    pub fn set_trailers_accepted(&mut self, accepted: bool) -> &mut Self {
+        self.trailers_accepted = accepted;
+        self
+    }
 
-        response += "\r\n";
-        response += &self.content;
+    /// Streams a body of exactly `length` bytes, sent with that
+    /// `Content-Length`. `body` runs once the headers have been written and
+    /// must write exactly `length` bytes.
+    pub fn stream_sized<F>(&mut self, length: u64, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut dyn Write) -> io::Result<()> + Send + 'static,
+    {
+        self.stream = Some(StreamingBody::Sized {
+            length,
+            body: Box::new(body),
+        });
+        self
+    }
+
+    /// Generates the body with `body` once the response is written, without
+    /// deciding up front how it is framed. Up to `threshold` bytes are
+    /// buffered: a body that ends within them is sent with a
+    /// `Content-Length`, and a longer one switches to chunked transfer
+    /// coding as soon as the threshold is passed, sending about `threshold`
+    /// bytes per chunk from then on.
+    ///
+    /// The framing is only known once the body has been generated, so the
+    /// head alone, as sent for `HEAD`, announces chunked coding.
+    ///
+    /// ```
+    /// use std::io::Write;
+    ///
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let send = |lines: usize| {
+    ///     let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    ///     response.stream_buffered(16, move |writer| {
+    ///         for n in 0..lines {
+    ///             writer.write_all(format!("line {n}\n").as_bytes())?;
+    ///         }
+    ///         Ok(())
+    ///     });
+    ///     let mut sent = Vec::new();
+    ///     response.write_to(&mut sent).unwrap();
+    ///     String::from_utf8(sent).unwrap()
+    /// };
+    ///
+    /// assert_eq!(send(2), "HTTP/1.1 200 OK\r\nContent-Length: 14\r\n\r\nline 0\nline 1\n");
+    /// assert_eq!(
+    ///     send(3),
+    ///     "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+    ///      15\r\nline 0\nline 1\nline 2\n\r\n0\r\n\r\n"
+    /// );
+    /// ```
+    pub fn stream_buffered<F>(&mut self, threshold: usize, body: F) -> &mut Self
+    where
+        F: FnOnce(&mut dyn Write) -> io::Result<()> + Send + 'static,
+    {
+        self.stream = Some(StreamingBody::Buffered {
+            threshold,
+            body: Box::new(body),
+        });
+        self
+    }
+
+    /// Streams the first `length` bytes of `reader` as the body, copying
+    /// them to the connection without buffering the whole body in memory.
+    pub fn stream_reader<R: Read + Send + 'static>(&mut self, reader: R, length: u64) -> &mut Self {
+        self.stream_sized(length, move |writer| copy_exactly(reader, length, writer))
+    }
+
+    /// Streams the next `length` bytes of `file`, from its current
+    /// position, as the body.
+    ///
+    /// When the server sends the response, the bytes are copied from the
+    /// file to the socket with [`io::copy`], which on Linux hands the copy
+    /// to the kernel with `sendfile(2)` instead of passing it through a
+    /// buffer in the process. Elsewhere it falls back to an ordinary copy.
+    ///
+    /// ```
+    /// use std::{
+    ///     fs::File,
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let path = std::env::temp_dir().join("stream-file-doctest.bin");
+    /// let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    /// std::fs::write(&path, &data)?;
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.get("/file", move |context| {
+    ///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///     response.send_file(File::open(&path).unwrap()).unwrap();
+    ///     response
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// stream.write_all(b"GET /file HTTP/1.1\r\nConnection: close\r\n\r\n")?;
+    /// let mut received = Vec::new();
+    /// stream.read_to_end(&mut received)?;
+    /// let head_end = received.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+    /// let head = String::from_utf8_lossy(&received[..head_end]);
+    /// assert!(head.contains("\r\nContent-Length: 200000\r\n"));
+    /// assert!(received[head_end..] == data[..]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn stream_file(&mut self, file: File, length: u64) -> &mut Self {
+        self.stream = Some(StreamingBody::File { file, length });
+        self
+    }
+
+    /// Streams `file` as the body, with the length taken from its metadata;
+    /// see [`stream_file`](Self::stream_file).
+    pub fn send_file(&mut self, file: File) -> io::Result<&mut Self> {
+        let length = file.metadata()?.len();
+        Ok(self.stream_file(file, length))
+    }
+
+    /// Serializes a buffered response. A streaming body set with
+    /// [`stream_chunked`](Self::stream_chunked) or
+    /// [`stream_sized`](Self::stream_sized) is not included; use
+    /// [`write_to`](Self::write_to) to send those.
+    ///
+    /// In debug builds, this and `write_to` panic if a `Content-Length`
+    /// header set by hand does not match the buffered body, which usually
+    /// means the body changed after the header was set. An empty body is
+    /// not checked, since a response to `HEAD` announces the length of a
+    /// body it leaves out.
+    ///
+    /// ```
+    /// use std::panic;
+    ///
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    /// response.add_header("Content-Length", "5").write_text("hello, world");
+    /// let serialized = panic::catch_unwind(panic::AssertUnwindSafe(|| response.to_bytes()));
+    /// assert_eq!(serialized.is_err(), cfg!(debug_assertions));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.debug_check_content_length();
+        let mut response = self.head().into_bytes();
+        response.extend_from_slice(&self.content);
         response
     }
+
+    /// Writes the response to `writer` and flushes it. Streaming bodies are
+    /// also flushed right after the headers.
+    ///
+    /// ```
+    /// use std::{fs::File, io::Write};
+    ///
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let send = |body: &dyn Fn(&mut HttpResponse)| -> std::io::Result<String> {
+    ///     let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    ///     body(&mut response);
+    ///     let mut sent = Vec::new();
+    ///     response.write_to(&mut sent)?;
+    ///     Ok(String::from_utf8(sent).unwrap())
+    /// };
+    /// let path = std::env::temp_dir().join("write-to-doctest.txt");
+    /// std::fs::write(&path, "file body")?;
+    ///
+    /// assert_eq!(
+    ///     send(&|response| {
+    ///         response.add_content("plain");
+    ///     })?,
+    ///     "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nplain"
+    /// );
+    /// assert_eq!(
+    ///     send(&|response| {
+    ///         response.stream_sized(5, |writer| writer.write_all(b"sized"));
+    ///     })?,
+    ///     "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nsized"
+    /// );
+    /// assert_eq!(
+    ///     send(&|response| {
+    ///         response.stream_chunked(|writer| writer.send_chunk(b"chunked"));
+    ///     })?,
+    ///     "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n7\r\nchunked\r\n0\r\n\r\n"
+    /// );
+    /// assert_eq!(
+    ///     send(&|response| {
+    ///         response.stream_buffered(16, |writer| writer.write_all(b"buffered"));
+    ///     })?,
+    ///     "HTTP/1.1 200 OK\r\nContent-Length: 8\r\n\r\nbuffered"
+    /// );
+    /// assert_eq!(
+    ///     send(&|response| {
+    ///         response.stream_file(File::open(&path).unwrap(), 4);
+    ///     })?,
+    ///     "HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nfile"
+    /// );
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn write_to<W: Write>(mut self, writer: &mut W) -> io::Result<()> {
+        let head = self.head();
+        match self.stream.take() {
+            // The framing depends on how long the body turns out to be, so
+            // the head is written once that is known.
+            Some(StreamingBody::Buffered { threshold, body }) if !self.close_delimited => {
+                let mut buffered = BufferedBodyWriter::new(writer, self.head_fields(), threshold);
+                body(&mut buffered)?;
+                buffered.finish()
+            }
+            // There is no framing to decide on for a close-delimited body.
+            Some(StreamingBody::Buffered { body, .. }) => {
+                writer.write_all(head.as_bytes())?;
+                body(writer)?;
+                writer.flush()
+            }
+            Some(StreamingBody::Chunked(body)) => {
+                writer.write_all(head.as_bytes())?;
+                writer.flush()?;
+                let mut chunked = if self.close_delimited {
+                    ChunkedWriter::close_delimited(writer)
+                } else {
+                    ChunkedWriter::new(writer, self.trailers_accepted)
+                };
+                body(&mut chunked)?;
+                chunked.finish()
+            }
+            Some(StreamingBody::Sized { body, .. }) => {
+                writer.write_all(head.as_bytes())?;
+                body(writer)?;
+                writer.flush()
+            }
+            Some(StreamingBody::File { file, length }) => {
+                writer.write_all(head.as_bytes())?;
+                copy_exactly(file, length, writer)?;
+                writer.flush()
+            }
+            None => {
+                self.debug_check_content_length();
+                writer.write_all(head.as_bytes())?;
+                writer.write_all(&self.content)?;
+                writer.flush()
+            }
+        }
+    }
+
+    /// Writes the response to `connection` like [`write_to`](Self::write_to),
+    /// except that a file body is copied straight to the underlying stream,
+    /// where `io::copy` can see both ends and use `sendfile(2)`.
+    pub(crate) fn write_to_connection<S: Read + Write>(
+        mut self,
+        connection: &mut Connection<S>,
+    ) -> io::Result<()> {
+        if !matches!(self.stream, Some(StreamingBody::File { .. })) {
+            return self.write_to(connection);
+        }
+        connection.write_all(self.head().as_bytes())?;
+        connection.flush()?;
+        if let Some(StreamingBody::File { file, length }) = self.stream.take() {
+            copy_exactly(file, length, connection.get_mut())?;
+        }
+        connection.flush()
+    }
+
+    fn debug_check_content_length(&self) {
+        if !cfg!(debug_assertions) || self.stream.is_some() || self.content.is_empty() {
+            return;
+        }
+        if let Some(length) = self.get_header("Content-Length") {
+            assert_eq!(
+                length.trim().parse::<usize>().ok(),
+                Some(self.content.len()),
+                "Content-Length header {:?} does not match the {}-byte body",
+                length,
+                self.content.len()
+            );
+        }
+    }
+
+    /// Writes only the status line and headers to `writer` and flushes it,
+    /// as the answer to a `HEAD` request. The framing headers still
+    /// describe the body that was left out.
+    pub fn write_head_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.head().as_bytes())?;
+        writer.flush()
+    }
+
+    /// The status line and headers, without the framing header or the blank
+    /// line that ends the head.
+    fn head_fields(&self) -> String {
+        let mut head = format!("{}\r\n", self.status_line);
+
+        for (name, value) in &self.headers {
+            head += &format!("{name}: {value}\r\n");
+        }
+        head
+    }
+
+    fn head(&self) -> String {
+        let mut head = self.head_fields();
+        match &self.stream {
+            Some(StreamingBody::Chunked(_) | StreamingBody::Buffered { .. })
+                if self.close_delimited => {}
+            Some(StreamingBody::Chunked(_) | StreamingBody::Buffered { .. }) => {
+                head += "Transfer-Encoding: chunked\r\n"
+            }
+            Some(StreamingBody::Sized { length, .. } | StreamingBody::File { length, .. }) => {
+                head += &format!("Content-Length: {length}\r\n");
+            }
+            // Interim responses, 204s and 304s cannot have a body, so they
+            // must not announce a length.
+            None if self.get_status_code() < 200
+                || self.get_status_code() == HttpStatus::NoContent.code()
+                || self.get_status_code() == HttpStatus::NotModified.code() => {}
+            None if !self.has_header("Content-Length") => {
+                head += &format!("Content-Length: {}\r\n", self.content.len());
+            }
+            None => {}
+        }
+        head += "\r\n";
+        head
+    }
+
+    pub fn get_header(&self, header_name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(header_name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Removes every header named `header_name`, ignoring case.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    /// response
+    ///     .add_header("Set-Cookie", "a=1")
+    ///     .add_header("X-Kept", "yes")
+    ///     .add_header("set-cookie", "b=2")
+    ///     .remove_header("SET-COOKIE");
+    ///
+    /// assert_eq!(response.get_header("Set-Cookie"), None);
+    /// assert_eq!(response.get_header("X-Kept"), Some("yes"));
+    /// ```
+    pub fn remove_header(&mut self, header_name: &str) -> &mut Self {
+        self.headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case(header_name));
+        self
+    }
+
+    /// Removes every header added so far. The framing header is still
+    /// added when the response is written.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    /// response
+    ///     .add_header("X-A", "1")
+    ///     .add_header("X-B", "2")
+    ///     .clear_headers()
+    ///     .add_header("X-C", "3");
+    ///
+    /// assert_eq!(
+    ///     response.to_bytes(),
+    ///     b"HTTP/1.1 200 OK\r\nX-C: 3\r\nContent-Length: 0\r\n\r\n"
+    /// );
+    /// ```
+    pub fn clear_headers(&mut self) -> &mut Self {
+        self.headers.clear();
+        self
+    }
+
+    /// Rewrites header names in their canonical casing, such as
+    /// `Content-Type` for `content-type`. Names with an irregular canonical
+    /// form, such as `ETag`, get that form, and the rest are capitalized
+    /// at each hyphen.
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{response::HttpResponse, HttpVersion};
+    ///
+    /// let mut response = HttpResponse::new(HttpVersion::Http1_1);
+    /// response
+    ///     .add_header("etag", "\"v1\"")
+    ///     .add_header("x-REQUEST-id", "7")
+    ///     .canonicalize_header_names();
+    /// assert_eq!(
+    ///     response.to_bytes(),
+    ///     b"HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nX-Request-Id: 7\r\nContent-Length: 0\r\n\r\n"
+    /// );
+    /// ```
+    pub fn canonicalize_header_names(&mut self) -> &mut Self {
+        for (name, _) in &mut self.headers {
+            *name = canonical_header_name(name);
+        }
+        self
+    }
+
+    fn has_header(&self, header_name: &str) -> bool {
+        self.headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(header_name))
+    }
+}
+
+impl fmt::Write for HttpResponse {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.append_content(s);
+        Ok(())
+    }
+}
+
+/// Headers whose canonical name is not capitalized at each hyphen.
+const IRREGULAR_HEADER_NAMES: &[&str] = &[
+    "Content-MD5",
+    "DNT",
+    "ETag",
+    "Sec-WebSocket-Accept",
+    "Sec-WebSocket-Extensions",
+    "Sec-WebSocket-Key",
+    "Sec-WebSocket-Protocol",
+    "Sec-WebSocket-Version",
+    "TE",
+    "WWW-Authenticate",
+    "X-XSS-Protection",
+];
+
+fn canonical_header_name(name: &str) -> String {
+    if let Some(irregular) = IRREGULAR_HEADER_NAMES
+        .iter()
+        .find(|irregular| irregular.eq_ignore_ascii_case(name))
+    {
+        return irregular.to_string();
+    }
+    let mut canonical = String::with_capacity(name.len());
+    let mut start_of_word = true;
+    for c in name.chars() {
+        canonical.push(if start_of_word {
+            c.to_ascii_uppercase()
+        } else {
+            c.to_ascii_lowercase()
+        });
+        start_of_word = c == '-';
+    }
+    canonical
+}
+
+fn copy_exactly<R: Read, W: Write + ?Sized>(
+    reader: R,
+    length: u64,
+    writer: &mut W,
+) -> io::Result<()> {
+    let copied = io::copy(&mut reader.take(length), writer)?;
+    if copied < length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
 }
@@ -0,0 +1,314 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use super::{
+    context::RequestContext,
+    request::{HttpRequest, HttpRequestMethod},
+    response::HttpResponse,
+};
+
+pub type Handler = Arc<dyn Fn(&RequestContext) -> HttpResponse + Send + Sync>;
+
+/// Values captured from the request path by a route pattern.
+#[derive(Default, Debug, Clone)]
+pub struct Params {
+    values: Vec<(String, String)>,
+}
+
+impl Params {
+    /// The value captured by a `:name` segment, or by a trailing `*` when
+    /// `name` is `"*"`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The captured value parsed as a `T`, or `None` if nothing was
+    /// captured under `name`. A handler can answer `400` on `Some(Err(_))`.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, request::HttpRequest, response::HttpResponse, router::Router,
+    /// };
+    ///
+    /// let mut router = Router::new();
+    /// router.add(None, "/users/:id", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// let params = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\n\r\n");
+    ///     let request =
+    ///         HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default()).unwrap();
+    ///     router.find(&request).unwrap().1
+    /// };
+    ///
+    /// assert_eq!(params("/users/42").get_as::<u64>("id"), Some(Ok(42)));
+    /// assert!(params("/users/me").get_as::<u64>("id").unwrap().is_err());
+    /// assert!(params("/users/42").get_as::<u64>("name").is_none());
+    /// ```
+    pub fn get_as<T: FromStr>(&self, name: &str) -> Option<Result<T, T::Err>> {
+        self.get(name).map(str::parse)
+    }
+}
+
+#[derive(Clone)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Wildcard,
+}
+
+pub struct Route {
+    method: Option<HttpRequestMethod>,
+    segments: Vec<Segment>,
+    handler: Handler,
+    access_log: bool,
+    timeout: Option<Duration>,
+    stream_body: bool,
+    conditional_get: bool,
+}
+
+impl Route {
+    /// Keeps requests to this route out of the access log.
+    pub fn without_access_log(&mut self) -> &mut Self {
+        self.access_log = false;
+        self
+    }
+
+    /// Answers `504 Gateway Timeout` if the handler has not produced a
+    /// response within `timeout`.
+    ///
+    /// Rust threads cannot be killed, so a handler that overruns keeps
+    /// running in the background until it returns, and its response is then
+    /// discarded. Handlers with side effects should not rely on being
+    /// stopped by the timeout, and can instead check
+    /// [`HttpRequest::time_remaining`] to stop early.
+    pub fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Leaves the request body on the connection for the handler to read
+    /// through [`HttpRequest::body_reader`] instead of buffering it first,
+    /// so large uploads can be streamed.
+    pub fn with_streaming_body(&mut self) -> &mut Self {
+        self.stream_body = true;
+        self
+    }
+
+    /// Answers a `GET` or `HEAD` with `304 Not Modified` when the client's
+    /// cached copy matches the `ETag` or `Last-Modified` the handler set on
+    /// its response, so that handlers need not check `If-None-Match` and
+    /// `If-Modified-Since` themselves. The handler still runs; see
+    /// [`revalidate`](super::conditional::revalidate).
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server
+    ///     .router()
+    ///     .add(None, "/doc", |context| {
+    ///         let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///         response.add_header("ETag", "\"v7\"").write_text("document");
+    ///         response
+    ///     })
+    ///     .with_conditional_get();
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let get = |headers: &str| -> std::io::Result<String> {
+    ///     let mut stream = TcpStream::connect(addr)?;
+    ///     write!(stream, "GET /doc HTTP/1.1\r\n{headers}Connection: close\r\n\r\n")?;
+    ///     let mut received = String::new();
+    ///     stream.read_to_string(&mut received)?;
+    ///     Ok(received)
+    /// };
+    /// assert!(get("")?.ends_with("\r\n\r\ndocument"));
+    /// let cached = get("If-None-Match: \"v7\"\r\n")?;
+    /// assert!(cached.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+    /// assert!(cached.contains("\r\nETag: \"v7\"\r\n"));
+    /// assert!(cached.ends_with("\r\n\r\n"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_conditional_get(&mut self) -> &mut Self {
+        self.conditional_get = true;
+        self
+    }
+
+    pub fn is_conditional_get(&self) -> bool {
+        self.conditional_get
+    }
+
+    pub fn streams_body(&self) -> bool {
+        self.stream_body
+    }
+
+    pub fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn get_handler(&self) -> &Handler {
+        &self.handler
+    }
+
+    pub fn is_access_logged(&self) -> bool {
+        self.access_log
+    }
+
+    fn matches(&self, method: &HttpRequestMethod, path: &str) -> Option<Params> {
+        if self
+            .method
+            .as_ref()
+            .is_some_and(|expected| expected != method)
+        {
+            return None;
+        }
+
+        let mut params = Params::default();
+        let mut parts = path.strip_prefix('/').unwrap_or(path).split('/');
+        for segment in &self.segments {
+            match segment {
+                Segment::Wildcard => {
+                    let rest: Vec<&str> = parts.collect();
+                    if rest.is_empty() {
+                        return None;
+                    }
+                    params.values.push(("*".to_string(), rest.join("/")));
+                    return Some(params);
+                }
+                Segment::Literal(literal) => {
+                    if parts.next()? != literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    let value = parts.next().filter(|value| !value.is_empty())?;
+                    params.values.push((name.clone(), value.to_string()));
+                }
+            }
+        }
+        parts.next().is_none().then_some(params)
+    }
+}
+
+/// Dispatches requests to handlers by method and path.
+///
+/// Patterns are matched segment by segment: `:name` captures one segment and
+/// a trailing `*` captures the rest of the path. Routes are tried in the
+/// order they were added.
+#[derive(Default)]
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a route for `pattern`. A `method` of `None` matches any method.
+    pub fn add<F>(
+        &mut self,
+        method: Option<HttpRequestMethod>,
+        pattern: &str,
+        handler: F,
+    ) -> &mut Route
+    where
+        F: Fn(&RequestContext) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.routes.push(Route {
+            method,
+            segments: parse_pattern(pattern),
+            handler: Arc::new(handler),
+            access_log: true,
+            timeout: None,
+            stream_body: false,
+            conditional_get: false,
+        });
+        self.routes.last_mut().unwrap()
+    }
+
+    /// Moves the routes of `router` under `prefix`, so that a route for
+    /// `/users/:id` mounted at `/api` matches `/api/users/42`. A route for
+    /// `/` matches the prefix itself.
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, request::HttpRequest, response::HttpResponse, router::Router,
+    /// };
+    ///
+    /// let mut api = Router::new();
+    /// api.add(None, "/users/:id", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// let mut router = Router::new();
+    /// router.mount("/api", api);
+    ///
+    /// let find = |path: &str| {
+    ///     let raw = format!("GET {path} HTTP/1.1\r\n\r\n");
+    ///     let request =
+    ///         HttpRequest::from_reader(&mut Cursor::new(raw), &ServerConfig::default()).unwrap();
+    ///     router.find(&request).map(|(_, params)| params)
+    /// };
+    ///
+    /// assert_eq!(find("/api/users/42").unwrap().get("id"), Some("42"));
+    /// assert!(find("/users/42").is_none());
+    /// ```
+    pub fn mount(&mut self, prefix: &str, router: Router) -> &mut Self {
+        let prefix = match prefix.trim_end_matches('/') {
+            "" => Vec::new(),
+            prefix => parse_pattern(prefix),
+        };
+        for mut route in router.routes {
+            if matches!(route.segments.as_slice(), [Segment::Literal(literal)] if literal.is_empty())
+            {
+                route.segments.clear();
+            }
+            let mut segments = prefix.clone();
+            segments.append(&mut route.segments);
+            route.segments = segments;
+            self.routes.push(route);
+        }
+        self
+    }
+
+    /// Finds the first route matching the request, ignoring any query string.
+    pub fn find(&self, request: &HttpRequest) -> Option<(&Route, Params)> {
+        let path = request.get_path();
+        let path = path.split_once('?').map_or(path, |(path, _)| path);
+        self.routes.iter().find_map(|route| {
+            route
+                .matches(request.get_method(), path)
+                .map(|params| (route, params))
+        })
+    }
+}
+
+fn parse_pattern(pattern: &str) -> Vec<Segment> {
+    pattern
+        .strip_prefix('/')
+        .unwrap_or(pattern)
+        .split('/')
+        .map(|segment| match segment {
+            "*" => Segment::Wildcard,
+            _ => match segment.strip_prefix(':') {
+                Some(name) => Segment::Param(name.to_string()),
+                None => Segment::Literal(segment.to_string()),
+            },
+        })
+        .collect()
+}
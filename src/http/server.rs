@@ -0,0 +1,1199 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Read, Write},
+    net::{SocketAddr, TcpListener, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::{Instant, SystemTime},
+};
+
+#[cfg(unix)]
+use std::{os::unix::net::UnixListener, path::Path};
+
+use super::{
+    access_log,
+    assets::Assets,
+    body::{BodyDecoder, BodyReader, ChunkLimits},
+    conditional,
+    config::ServerConfig,
+    connection::Connection,
+    context::RequestContext,
+    errors::HttpError,
+    json,
+    listener::{Accepted, Listener},
+    metrics,
+    pool::ThreadPool,
+    proxy::ReverseProxy,
+    range::ranged_response,
+    redirect,
+    request::{starts_request_line, HttpRequest, HttpRequestMethod},
+    response::{HttpResponse, HttpStatus},
+    router::{Handler, Params, Route, Router},
+    session::{SessionStore, Sessions},
+    shutdown::{ShutdownHandle, TrackedConnection, Tracker},
+    stats::{ServerStats, Stats},
+    upgrade::{self, UpgradeHandler, Upgraded},
+    HttpVersion,
+};
+
+/// How many 1 KiB reads of an overloaded connection's request are
+/// discarded before it is closed.
+const OVERLOAD_DRAIN_READS: usize = 8;
+
+/// The parts of a server shared by every connection it handles.
+struct Service {
+    config: ServerConfig,
+    router: Router,
+    proxy: Option<ReverseProxy>,
+    stats: Arc<Stats>,
+    connections: Arc<Tracker>,
+    upgrades: Vec<(String, UpgradeHandler)>,
+    assets: Assets,
+    /// The [`RequestContext::request_id`] last handed out.
+    request_ids: AtomicU64,
+    sessions: Option<Arc<Sessions>>,
+    validators: Vec<Validator>,
+}
+
+/// Checks a request before it is routed; see [`Server::validate`].
+type Validator = Box<dyn Fn(&HttpRequest) -> Option<HttpResponse> + Send + Sync>;
+
+/// The run of identical requests a connection is on, for
+/// [`ServerConfig::max_repeated_requests`].
+#[derive(Default)]
+struct Repeats {
+    last: Option<(HttpRequestMethod, String)>,
+    count: usize,
+}
+
+impl Repeats {
+    /// Counts `request`, returning how many identical requests in a row it
+    /// makes.
+    fn count(&mut self, request: &HttpRequest) -> usize {
+        let key = (request.get_method().clone(), request.get_path().to_string());
+        if self.last.as_ref() == Some(&key) {
+            self.count += 1;
+        } else {
+            self.last = Some(key);
+            self.count = 1;
+        }
+        self.count
+    }
+}
+
+pub struct Server {
+    listener: Listener,
+    pool: ThreadPool,
+    service: Service,
+}
+
+impl Server {
+    pub fn bind<A: ToSocketAddrs>(addr: A, config: ServerConfig) -> io::Result<Self> {
+        Self::with_listener(Listener::Tcp(TcpListener::bind(addr)?), config)
+    }
+
+    /// Listens on a Unix domain socket created at `path`, for clients on
+    /// the same host such as a reverse proxy in front of the server. The
+    /// socket file must not exist yet, and is left behind when the server
+    /// stops.
+    ///
+    /// Connections over the socket have no peer address, so the access
+    /// log shows `-` as the client, and [`local_addr`](Self::local_addr)
+    /// fails.
+    ///
+    /// ```
+    /// # #[cfg(unix)]
+    /// # {
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     os::unix::net::UnixStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let path = std::env::temp_dir().join(format!("bind-unix-doctest-{}.sock", std::process::id()));
+    /// let _ = std::fs::remove_file(&path);
+    /// let mut server = Server::bind_unix(&path, ServerConfig::default())?;
+    /// server.get("/", |context| {
+    ///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///     response.write_text("over a socket file");
+    ///     response
+    /// });
+    /// thread::spawn(move || server.run());
+    ///
+    /// let mut stream = UnixStream::connect(&path)?;
+    /// stream.write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")?;
+    /// let mut received = String::new();
+    /// stream.read_to_string(&mut received)?;
+    /// assert!(received.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// assert!(received.ends_with("\r\n\r\nover a socket file"));
+    /// std::fs::remove_file(&path)?;
+    /// # }
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn bind_unix<P: AsRef<Path>>(path: P, config: ServerConfig) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        Self::with_listener(Listener::Unix(UnixListener::bind(&path)?, path), config)
+    }
+
+    fn with_listener(listener: Listener, config: ServerConfig) -> io::Result<Self> {
+        Ok(Self {
+            listener,
+            pool: ThreadPool::with_max_queued(
+                config.worker_threads,
+                &config.worker_name_prefix,
+                config.max_queued_connections,
+            )?,
+            service: Service {
+                config,
+                router: Router::new(),
+                proxy: None,
+                stats: Arc::default(),
+                connections: Arc::default(),
+                upgrades: Vec::new(),
+                assets: Assets::default(),
+                request_ids: AtomicU64::new(0),
+                validators: Vec::new(),
+                sessions: None,
+            },
+        })
+    }
+
+    /// The address the server is listening on, useful after binding to
+    /// port `0`.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// A snapshot of the server's connection and request counters.
+    ///
+    /// ```
+    /// use std::thread;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     client::HttpClient, config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.get("/", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// assert_eq!(server.stats().requests_handled, 0);
+    ///
+    /// let addr = server.local_addr()?;
+    /// let stats = server.stats_handle();
+    /// thread::spawn(move || server.run());
+    ///
+    /// HttpClient::get(&format!("http://{addr}/")).unwrap();
+    /// HttpClient::get(&format!("http://{addr}/")).unwrap();
+    /// HttpClient::get(&format!("http://{addr}/missing")).unwrap();
+    ///
+    /// let snapshot = stats.snapshot();
+    /// assert_eq!(snapshot.connections_accepted, 3);
+    /// assert_eq!(snapshot.requests_handled, 3);
+    /// assert_eq!(snapshot.responses_2xx, 2);
+    /// assert_eq!(snapshot.responses_4xx, 1);
+    /// assert_eq!(snapshot.responses_5xx, 0);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn stats(&self) -> ServerStats {
+        self.service.stats.snapshot()
+    }
+
+    /// A handle on the server's counters that stays readable once
+    /// [`run`](Self::run) has taken the server, for example from a
+    /// `/metrics` handler.
+    pub fn stats_handle(&self) -> Arc<Stats> {
+        Arc::clone(&self.service.stats)
+    }
+
+    /// A handle for shutting the server down gracefully once
+    /// [`run`](Self::run) has taken it; see [`ShutdownHandle::shutdown`].
+    pub fn shutdown_handle(&self) -> io::Result<ShutdownHandle> {
+        Ok(ShutdownHandle {
+            tracker: Arc::clone(&self.service.connections),
+            wake: Arc::from(self.listener.waker()?),
+        })
+    }
+
+    pub fn router(&mut self) -> &mut Router {
+        &mut self.service.router
+    }
+
+    /// Adds a `GET` route; shorthand for [`Router::add`].
+    ///
+    /// ```
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server
+    ///     .get("/users/:id", |context| {
+    ///         let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///         response.write_text(context.params.get("id").unwrap_or_default());
+    ///         response
+    ///     })
+    ///     .post("/users", |context| {
+    ///         HttpResponse::new(context.request.get_http_version().to_owned())
+    ///     })
+    ///     .delete("/users/:id", |context| {
+    ///         HttpResponse::no_content(context.request.get_http_version().to_owned())
+    ///     });
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn get<F>(&mut self, path: &str, handler: F) -> &mut Self
+    where
+        F: Fn(&RequestContext) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(HttpRequestMethod::Get, path, handler)
+    }
+
+    pub fn post<F>(&mut self, path: &str, handler: F) -> &mut Self
+    where
+        F: Fn(&RequestContext) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(HttpRequestMethod::Post, path, handler)
+    }
+
+    pub fn put<F>(&mut self, path: &str, handler: F) -> &mut Self
+    where
+        F: Fn(&RequestContext) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(HttpRequestMethod::Put, path, handler)
+    }
+
+    pub fn patch<F>(&mut self, path: &str, handler: F) -> &mut Self
+    where
+        F: Fn(&RequestContext) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(HttpRequestMethod::Patch, path, handler)
+    }
+
+    pub fn delete<F>(&mut self, path: &str, handler: F) -> &mut Self
+    where
+        F: Fn(&RequestContext) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.route(HttpRequestMethod::Delete, path, handler)
+    }
+
+    /// Adds a route for `method`. Use [`router`](Self::router) instead to
+    /// match any method or to configure the route further.
+    pub fn route<F>(&mut self, method: HttpRequestMethod, path: &str, handler: F) -> &mut Self
+    where
+        F: Fn(&RequestContext) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.service.router.add(Some(method), path, handler);
+        self
+    }
+
+    /// Serves the routes of `router` under `prefix`; see [`Router::mount`].
+    pub fn mount(&mut self, prefix: &str, router: Router) -> &mut Self {
+        self.service.router.mount(prefix, router);
+        self
+    }
+
+    /// Forwards every request to `proxy` instead of routing it.
+    pub fn set_proxy(&mut self, proxy: ReverseProxy) -> &mut Self {
+        self.service.proxy = Some(proxy);
+        self
+    }
+
+    /// Hands requests asking to switch to `protocol` with `Connection:
+    /// Upgrade` and `Upgrade` headers to `handler` instead of routing them.
+    /// The handler sends the `101 Switching Protocols` and takes over the
+    /// connection through [`Upgraded::accept`]. Requests for protocols
+    /// without a handler are routed as usual.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{BufRead, BufReader, Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{config::ServerConfig, server::Server};
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// // Echoes back the first line sent over the new protocol.
+    /// server.upgrade("echo", |request, mut upgraded| {
+    ///     let greeting = request.get_header("X-Greeting").unwrap_or_default();
+    ///     upgraded.response().add_header("X-Greeting", greeting);
+    ///     let Ok(mut stream) = upgraded.accept() else {
+    ///         return;
+    ///     };
+    ///     let mut line = Vec::new();
+    ///     for byte in (&mut stream).bytes().map_while(Result::ok) {
+    ///         line.push(byte);
+    ///         if byte == b'\n' {
+    ///             break;
+    ///         }
+    ///     }
+    ///     let _ = stream.write_all(&line);
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// // Header names are matched ignoring case.
+    /// stream.write_all(
+    ///     b"GET /chat HTTP/1.1\r\nconnection: Upgrade\r\nupgrade: h2c, echo\r\n\
+    ///       X-Greeting: hi\r\n\r\nhello\n",
+    /// )?;
+    /// let mut received = String::new();
+    /// BufReader::new(stream).read_to_string(&mut received)?;
+    /// let (head, rest) = received.split_once("\r\n\r\n").unwrap();
+    /// assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+    /// assert!(head.contains("\r\nConnection: Upgrade\r\n"));
+    /// assert!(head.contains("\r\nUpgrade: echo\r\n"));
+    /// assert!(head.ends_with("\r\nX-Greeting: hi"));
+    /// assert_eq!(rest, "hello\n");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn upgrade<F>(&mut self, protocol: &str, handler: F) -> &mut Self
+    where
+        F: Fn(&HttpRequest, Upgraded) + Send + Sync + 'static,
+    {
+        self.service
+            .upgrades
+            .push((protocol.to_string(), Arc::new(handler)));
+        self
+    }
+
+    /// Runs `validator` on every request as soon as it is read, before it
+    /// is upgraded, proxied or routed. A response it returns is sent
+    /// instead, for simple gatekeeping such as blocking addresses or
+    /// requiring a header; `None` lets the request through. Validators run
+    /// in the order they were added, and the first response wins.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig,
+    ///     response::{HttpResponse, HttpStatus},
+    ///     server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.validate(|request| {
+    ///     if request.get_header("X-Api-Key").is_some_and(|key| key == "secret") {
+    ///         return None;
+    ///     }
+    ///     let version = request.get_http_version().to_owned();
+    ///     Some(HttpResponse::from_status(version, HttpStatus::Forbidden))
+    /// });
+    /// server.get("/data", |context| {
+    ///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///     response.write_text("data");
+    ///     response
+    /// });
+    /// server.upgrade("echo", |_, upgraded| {
+    ///     let _ = upgraded.accept();
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let get = |headers: &str| -> std::io::Result<String> {
+    ///     let mut stream = TcpStream::connect(addr)?;
+    ///     write!(stream, "GET /data HTTP/1.1\r\n{headers}Connection: close\r\n\r\n")?;
+    ///     let mut received = String::new();
+    ///     stream.read_to_string(&mut received)?;
+    ///     Ok(received)
+    /// };
+    /// assert!(get("")?.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    /// assert!(get("X-Api-Key: guess\r\n")?.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    /// let allowed = get("X-Api-Key: secret\r\n")?;
+    /// assert!(allowed.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// assert!(allowed.ends_with("\r\n\r\ndata"));
+    ///
+    /// // Requests that would be upgraded or proxied are checked too.
+    /// let upgrade = "Connection: Upgrade\r\nUpgrade: echo\r\n";
+    /// assert!(get(upgrade)?.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    /// let upgraded = get(&format!("{upgrade}X-Api-Key: secret\r\n"))?;
+    /// assert!(upgraded.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn validate<F>(&mut self, validator: F) -> &mut Self
+    where
+        F: Fn(&HttpRequest) -> Option<HttpResponse> + Send + Sync + 'static,
+    {
+        self.service.validators.push(Box::new(validator));
+        self
+    }
+
+    /// Registers a `GET` route at `path` that serves `data`, typically an
+    /// asset embedded with [`include_bytes!`], without any file I/O. Range
+    /// requests are supported as for [`ranged_response`].
+    ///
+    /// ```
+    /// use std::thread;
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     client::HttpClient, config::ServerConfig, server::Server,
+    /// };
+    ///
+    /// // Stands in for `include_bytes!("favicon.ico")`.
+    /// static FAVICON: &[u8] = b"\x00\x00\x01\x00\xff";
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.static_bytes("/favicon.ico", FAVICON, "image/x-icon");
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let response = HttpClient::get(&format!("http://{addr}/favicon.ico")).unwrap();
+    /// assert_eq!(response.get_status_code(), 200);
+    /// assert_eq!(response.get_header("Content-Type").unwrap(), "image/x-icon");
+    /// assert_eq!(response.get_body(), FAVICON);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn static_bytes(
+        &mut self,
+        path: &str,
+        data: &'static [u8],
+        content_type: &str,
+    ) -> &mut Route {
+        let content_type = content_type.to_string();
+        self.service
+            .router
+            .add(Some(HttpRequestMethod::Get), path, move |context| {
+                ranged_response(context.request, data, &content_type)
+            })
+    }
+
+    /// Serves embedded assets by path, each with its content type, ahead of
+    /// any route. `GET` and `HEAD` are answered with range support as for
+    /// [`static_bytes`](Self::static_bytes), plus an `ETag` derived from the
+    /// content so that clients can revalidate with `If-None-Match` and get a
+    /// `304 Not Modified`.
+    ///
+    /// ```
+    /// use std::{
+    ///     collections::HashMap,
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     client::HttpClient, config::ServerConfig, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.assets(HashMap::from([
+    ///     ("/index.html".to_string(), (&b"<h1>hi</h1>"[..], "text/html")),
+    ///     ("/app.js".to_string(), (&b"run()"[..], "text/javascript")),
+    /// ]));
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let page = HttpClient::get(&format!("http://{addr}/index.html")).unwrap();
+    /// assert_eq!(page.get_header("Content-Type").unwrap(), "text/html");
+    /// assert_eq!(page.get_body(), b"<h1>hi</h1>");
+    /// let script = HttpClient::get(&format!("http://{addr}/app.js?v=2")).unwrap();
+    /// assert_eq!(script.get_body(), b"run()");
+    ///
+    /// let etag = script.get_header("ETag").unwrap();
+    /// assert_eq!(etag, "\"5-02fcae88bd120f59\"");
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// write!(
+    ///     stream,
+    ///     "GET /app.js HTTP/1.1\r\nIf-None-Match: {etag}\r\nConnection: close\r\n\r\n"
+    /// )?;
+    /// let mut revalidated = String::new();
+    /// stream.read_to_string(&mut revalidated)?;
+    /// assert!(revalidated.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+    /// assert!(revalidated.contains(&format!("\r\nETag: {etag}\r\n")));
+    ///
+    /// let mut stream = TcpStream::connect(addr)?;
+    /// stream.write_all(b"GET /index.html HTTP/1.1\r\nRange: bytes=4-5\r\nConnection: close\r\n\r\n")?;
+    /// let mut partial = String::new();
+    /// stream.read_to_string(&mut partial)?;
+    /// assert!(partial.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+    /// assert!(partial.contains("\r\nContent-Range: bytes 4-5/11\r\n"));
+    /// assert!(partial.ends_with("\r\n\r\nhi"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn assets(&mut self, assets: HashMap<String, (&'static [u8], &'static str)>) -> &mut Self {
+        for (path, (data, content_type)) in assets {
+            self.service.assets.insert(path, data, content_type);
+        }
+        self
+    }
+
+    /// Gives handlers the client's [`Session`](super::session::Session)
+    /// from `store`, found by the id in the cookie `cookie`.
+    pub fn sessions<S: SessionStore + 'static>(&mut self, cookie: &str, store: S) -> &mut Self {
+        self.service.sessions = Some(Arc::new(Sessions {
+            cookie: cookie.to_string(),
+            store: Box::new(store),
+        }));
+        self
+    }
+
+    /// Registers a liveness endpoint at `path` that answers `200 ok`.
+    ///
+    /// Health checks are typically polled every few seconds by a load
+    /// balancer, so they are left out of the access log.
+    ///
+    /// ```
+    /// use std::{io::Cursor, thread};
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     client::HttpClient, config::ServerConfig, request::HttpRequest, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.health_check("/healthz");
+    /// server.get("/", |_| unreachable!());
+    ///
+    /// let config = ServerConfig::default();
+    /// let parse = |raw: &str| HttpRequest::from_reader(&mut Cursor::new(raw), &config).unwrap();
+    /// let health = parse("GET /healthz HTTP/1.1\r\n\r\n");
+    /// let index = parse("GET / HTTP/1.1\r\n\r\n");
+    /// assert!(!server.router().find(&health).unwrap().0.is_access_logged());
+    /// assert!(server.router().find(&index).unwrap().0.is_access_logged());
+    ///
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    /// let response = HttpClient::get(&format!("http://{addr}/healthz")).unwrap();
+    /// assert_eq!(response.get_status_code(), 200);
+    /// assert_eq!(response.get_body(), b"ok");
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn health_check(&mut self, path: &str) -> &mut Route {
+        self.service
+            .router
+            .add(Some(HttpRequestMethod::Get), path, |context| {
+                let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+                response.write_text("ok");
+                response
+            })
+            .without_access_log()
+    }
+
+    /// Registers an endpoint at `path` that answers with the server's
+    /// [`stats`](Self::stats) in the Prometheus text format; see
+    /// [`metrics::render`]. Like health checks, scrapes are left out of the
+    /// access log.
+    pub fn metrics_endpoint(&mut self, path: &str) -> &mut Route {
+        let stats = self.stats_handle();
+        self.service
+            .router
+            .add(Some(HttpRequestMethod::Get), path, move |context| {
+                let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+                response
+                    .add_header("Content-Type", metrics::CONTENT_TYPE)
+                    .add_content(&metrics::render(&stats.snapshot()));
+                response
+            })
+            .without_access_log()
+    }
+
+    /// Registers a debugging endpoint at `path` that answers with the
+    /// request's headers as a JSON object. Repeated headers are combined
+    /// into one comma-separated value.
+    pub fn headers_endpoint(&mut self, path: &str) -> &mut Route {
+        self.service
+            .router
+            .add(Some(HttpRequestMethod::Get), path, |context| {
+                let mut headers: Vec<(&str, String)> = Vec::new();
+                for (name, value) in context.request.get_headers() {
+                    match headers
+                        .iter_mut()
+                        .find(|(seen, _)| seen.eq_ignore_ascii_case(name))
+                    {
+                        Some((_, combined)) => *combined += &format!(", {value}"),
+                        None => headers.push((name, value.clone())),
+                    }
+                }
+
+                let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+                response.write_json(&json::object(
+                    headers.iter().map(|(name, value)| (*name, value.as_str())),
+                ));
+                response
+            })
+    }
+
+    /// Accepts connections, handling them on the worker pool, until the
+    /// server is shut down through a [`ShutdownHandle`]. It then returns
+    /// once the workers have finished with the connections they were
+    /// given.
+    ///
+    /// A request without `Content-Length` or `Transfer-Encoding` has no
+    /// body, so data already received after its head that cannot be the
+    /// start of a pipelined request is answered with `400 Bad Request` and
+    /// the connection is closed.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.get("/", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// server.post("/", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let exchange = |raw: &str| -> std::io::Result<String> {
+    ///     let mut stream = TcpStream::connect(addr)?;
+    ///     stream.write_all(raw.as_bytes())?;
+    ///     let mut received = String::new();
+    ///     stream.read_to_string(&mut received)?;
+    ///     Ok(received)
+    /// };
+    ///
+    /// let smuggled = exchange("GET / HTTP/1.1\r\n\r\nhello")?;
+    /// assert!(smuggled.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    ///
+    /// let pipelined =
+    ///     exchange("GET / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\nConnection: close\r\n\r\n")?;
+    /// assert_eq!(pipelined.matches("HTTP/1.1 200 OK\r\n").count(), 2);
+    ///
+    /// // Header names are case-insensitive, so these bodies are delimited.
+    /// let lowercase = exchange(
+    ///     "POST / HTTP/1.1\r\ncontent-length: 5\r\n\r\nhello\
+    ///      POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n\
+    ///      GET / HTTP/1.1\r\nConnection: close\r\n\r\n",
+    /// )?;
+    /// assert_eq!(lowercase.matches("HTTP/1.1 200 OK\r\n").count(), 3);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    ///
+    /// A client that closes the connection before sending anything, as
+    /// port scanners and health probes do, is not answered and does not
+    /// count as a failed request:
+    ///
+    /// ```
+    /// use std::{net::TcpStream, thread, time::Duration};
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     client::HttpClient, config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.get("/", |context| {
+    ///     HttpResponse::new(context.request.get_http_version().to_owned())
+    /// });
+    /// let addr = server.local_addr()?;
+    /// let stats = server.stats_handle();
+    /// thread::spawn(move || server.run());
+    ///
+    /// drop(TcpStream::connect(addr)?);
+    /// while stats.snapshot().connections_accepted == 0 || stats.snapshot().active_connections > 0 {
+    ///     thread::sleep(Duration::from_millis(5));
+    /// }
+    /// assert!(stats.snapshot().responses_by_status.is_empty());
+    ///
+    /// HttpClient::get(&format!("http://{addr}/")).unwrap();
+    /// assert_eq!(stats.snapshot().responses_by_status, vec![(200, 1)]);
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn run(self) {
+        let service = Arc::new(self.service);
+        match &self.listener {
+            Listener::Tcp(listener) => accept(&service, &self.pool, listener.incoming()),
+            #[cfg(unix)]
+            Listener::Unix(listener, _) => accept(&service, &self.pool, listener.incoming()),
+        }
+    }
+}
+
+/// Hands the connections from `incoming` to the pool until the server is
+/// shut down.
+fn accept<S: Accepted>(
+    service: &Arc<Service>,
+    pool: &ThreadPool,
+    incoming: impl Iterator<Item = io::Result<S>>,
+) {
+    for stream in incoming {
+        match stream {
+            Ok(stream) => {
+                if service.connections.is_shutting_down() {
+                    break;
+                }
+                service.stats.connection_accepted();
+                // This loop is the only one queueing connections, so the
+                // pool cannot fill up between checking and queueing.
+                if pool.is_full() {
+                    service.overloaded(stream);
+                    continue;
+                }
+                let tracked = match stream.try_clone() {
+                    Ok(clone) => service.connections.track(clone),
+                    Err(e) => {
+                        println!("error: {}", e);
+                        continue;
+                    }
+                };
+                let service = Arc::clone(service);
+                pool.execute(move || {
+                    let peer_addr = stream.peer_addr();
+                    if let Err(e) = stream.set_read_timeout(service.config.idle_timeout) {
+                        println!("error: {}", e);
+                    }
+                    let connection = Connection::new(stream, service.config.write_buffer_size);
+                    service.handle_connection(connection, peer_addr, &tracked);
+                });
+            }
+            Err(e) => {
+                println!("error: {}", e);
+            }
+        }
+    }
+}
+
+impl Service {
+    /// Serves requests on `connection` until the client or the keep-alive
+    /// policy closes it.
+    fn handle_connection<S: Read + Write + Send + 'static>(
+        &self,
+        mut connection: Connection<S>,
+        peer_addr: Option<SocketAddr>,
+        tracked: &TrackedConnection,
+    ) {
+        println!("accepted new connection");
+        let _active = self.stats.connection_opened();
+        let mut repeats = Repeats::default();
+        // Every request head on the connection is read through one buffer.
+        let mut scratch = Vec::with_capacity(256);
+        let max_requests = if self.config.keep_alive {
+            self.config.max_requests_per_connection.max(1)
+        } else {
+            1
+        };
+
+        for served in 1..=max_requests {
+            // The first request counts as in flight from the moment the
+            // connection is accepted, later ones only once they arrive.
+            if served > 1 && !tracked.set_idle() {
+                return;
+            }
+            // A client closing the connection or leaving it idle past the
+            // read timeout between requests is not an error.
+            if !matches!(connection.fill_buf(), Ok(buffer) if !buffer.is_empty()) {
+                return;
+            }
+            tracked.set_busy();
+            let remaining = max_requests - served;
+            match self.serve_request(connection, remaining, peer_addr, &mut repeats, &mut scratch) {
+                Some(kept_alive) => connection = kept_alive,
+                None => return,
+            }
+        }
+    }
+
+    /// Serves one request, handing the connection back if it is to be kept
+    /// alive for another one.
+    fn serve_request<S: Read + Write + Send + 'static>(
+        &self,
+        mut connection: Connection<S>,
+        mut remaining: usize,
+        peer_addr: Option<SocketAddr>,
+        repeats: &mut Repeats,
+        scratch: &mut Vec<u8>,
+    ) -> Option<Connection<S>> {
+        let parsed = HttpRequest::from_reader_with(&mut connection, &self.config, scratch);
+        let mut request = match parsed {
+            Ok(request) => request,
+            Err(e) => {
+                self.reject(&mut connection, &e);
+                return None;
+            }
+        };
+        // Validators see every request, including those that are upgraded
+        // or proxied, so they cannot be bypassed.
+        let rejected = self
+            .validators
+            .iter()
+            .find_map(|validator| validator(&request));
+        // The bytes after an upgrade request belong to the new protocol.
+        let upgrade =
+            upgrade::requested_protocol(&request, &self.upgrades).filter(|_| rejected.is_none());
+        if let Some((protocol, handler)) = upgrade {
+            self.stats.request_handled();
+            handler(&request, Upgraded::new(protocol, Box::new(connection)));
+            return None;
+        }
+
+        if let Some(limit) = self.config.max_repeated_requests {
+            let count = repeats.count(&request);
+            if count > limit {
+                println!(
+                    "closing connection after {} identical requests: {} {}",
+                    count,
+                    request.get_method().as_str(),
+                    request.get_path()
+                );
+                remaining = 0;
+            }
+        }
+
+        // Without a length, whatever follows the head is the next request,
+        // so bytes that cannot start one were meant as a body the server
+        // cannot delimit, possibly to smuggle a request past a proxy.
+        if request.get_header("Content-Length").is_none()
+            && request.get_header("Transfer-Encoding").is_none()
+            && !connection.buffered().is_empty()
+            && !starts_request_line(connection.buffered())
+        {
+            self.reject(&mut connection, &HttpError::UnexpectedTrailingData);
+            return None;
+        }
+
+        // The proxy streams request bodies upstream itself.
+        if let Some(proxy) = self.proxy.as_ref().filter(|_| rejected.is_none()) {
+            self.stats.request_handled();
+            if let Err(e) = proxy.forward(&request, &mut connection, peer_addr, &self.config) {
+                println!("proxy error: {}", e);
+            }
+            return None;
+        }
+
+        let found = self.router.find(&request);
+        // A rejected client waiting to be told to send its body never is,
+        // so the body is left unread and the connection closed.
+        let stream_body = self.config.stream_request_bodies
+            || found
+                .as_ref()
+                .is_some_and(|(route, _)| route.streams_body())
+            || (rejected.is_some() && request.expects_continue());
+        // A client that sent `Expect: 100-continue` holds the body back
+        // until it is told to go ahead.
+        if request.expects_continue() && rejected.is_none() {
+            let response = HttpResponse::from_status(HttpVersion::Http1_1, HttpStatus::Continue);
+            let sent = connection
+                .write_all(&response.to_bytes())
+                .and_then(|()| connection.flush());
+            if let Err(e) = sent {
+                println!("error: {}", e);
+                return None;
+            }
+        }
+        let decoder = if stream_body {
+            BodyDecoder::for_headers(request.get_headers(), ChunkLimits::new(&self.config))
+                .map(Some)
+        } else {
+            request.read_body(&mut connection).map(|()| None)
+        };
+        let decoder = match decoder {
+            Ok(decoder) => decoder,
+            Err(e) => {
+                self.reject(&mut connection, &e);
+                return None;
+            }
+        };
+
+        // The handler reaches the connection through handles for reading a
+        // streamed body and for sending interim responses, which are taken
+        // back once it is done.
+        let shared = Arc::new(Mutex::new(connection));
+        let body_finished = decoder.map(|decoder| {
+            let (reader, body_finished) = BodyReader::new(shared.clone(), decoder);
+            request.set_body_reader(reader);
+            body_finished
+        });
+        let interim = Arc::clone(&shared);
+        request.set_interim_writer(Box::new(move |response| {
+            let mut connection = interim.lock().unwrap_or_else(PoisonError::into_inner);
+            connection.write_all(&response.to_bytes())?;
+            connection.flush()
+        }));
+        request.set_deadline(self.deadline(found.as_ref()));
+        let (response, access_logged) = self.handle_request(&request, found, rejected, peer_addr);
+        drop(request.body_reader());
+        drop(request.take_interim_writer());
+
+        // A handler still running past its timeout may hold the connection,
+        // and one that left part of the body unread leaves it unusable.
+        let body_finished = match body_finished {
+            Some(finished) => finished.load(Ordering::Acquire),
+            None => true,
+        };
+        let reusable = Arc::strong_count(&shared) == 1 && body_finished;
+        let keep_alive = {
+            let remaining = if reusable { remaining } else { 0 };
+            let mut connection = shared.lock().unwrap_or_else(PoisonError::into_inner);
+            let logged_peer = access_logged.then_some(peer_addr);
+            self.send_response(&request, response, logged_peer, remaining, &mut *connection)
+        };
+        if !keep_alive {
+            return None;
+        }
+        let shared = Arc::try_unwrap(shared).ok()?;
+        Some(shared.into_inner().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// When the handler for `found`, starting now, has to respond by.
+    fn deadline(&self, found: Option<&(&Route, Params)>) -> Option<Instant> {
+        let (route, _) = found?;
+        let timeout = route.get_timeout().or(self.config.handler_timeout)?;
+        Some(Instant::now() + timeout)
+    }
+
+    /// Answers a request that could not be read and should not be followed
+    /// by another on the same connection.
+    fn reject<W: Write>(&self, connection: &mut W, e: &HttpError) {
+        println!("bad request: {}", e);
+        let mut response = HttpResponse::from_status(HttpVersion::Http1_1, e.status());
+        response.add_header("Connection", "close");
+        if !self.apply_error_page(&mut response) {
+            response.write_text(&e.to_string());
+        }
+        if self.config.omit_reason_phrase {
+            response.omit_reason_phrase();
+        }
+        self.stats.response_sent(response.get_status_code());
+        if let Err(e) = response.write_to(connection) {
+            println!("error: {}", e);
+        }
+    }
+
+    /// Turns away a connection the pool has no room for, without waiting
+    /// for its request, so that the accept loop is not held up.
+    fn overloaded<S: Accepted>(&self, mut stream: S) {
+        println!("turning away connection: all workers busy");
+        let mut response =
+            HttpResponse::from_status(HttpVersion::Http1_1, HttpStatus::ServiceUnavailable);
+        response
+            .add_header(
+                "Retry-After",
+                &self.config.overload_retry_after.as_secs().to_string(),
+            )
+            .add_header("Connection", "close");
+        self.apply_error_page(&mut response);
+        if self.config.omit_reason_phrase {
+            response.omit_reason_phrase();
+        }
+        self.stats.response_sent(response.get_status_code());
+        if let Err(e) = response.write_to(&mut stream) {
+            println!("error: {}", e);
+        }
+        // Closing with unread data resets the connection, which can make
+        // the client drop the response, so what of the request has already
+        // arrived is read first. The read is bounded so that a client that
+        // keeps sending cannot hold up the accept loop.
+        if stream.set_nonblocking(true).is_ok() {
+            let mut discarded = [0; 1024];
+            for _ in 0..OVERLOAD_DRAIN_READS {
+                if !matches!(stream.read(&mut discarded), Ok(read) if read > 0) {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Adds the connection management headers and writes the response,
+    /// returning whether the connection stays open. `remaining` is the
+    /// number of further requests the connection may serve. The response
+    /// is access logged, as finally sent, if `logged_peer` is `Some` with
+    /// the client's address.
+    fn send_response<S: Read + Write>(
+        &self,
+        request: &HttpRequest,
+        mut response: HttpResponse,
+        logged_peer: Option<Option<SocketAddr>>,
+        remaining: usize,
+        connection: &mut Connection<S>,
+    ) -> bool {
+        response.apply_auto_etag(request);
+        self.apply_error_page(&mut response);
+        if let Some(peer_addr) = logged_peer {
+            println!(
+                "{}",
+                access_log::format_entry(
+                    self.config.access_log_format,
+                    request,
+                    &response,
+                    peer_addr,
+                    SystemTime::now(),
+                )
+            );
+        }
+        if self.config.omit_reason_phrase {
+            response.omit_reason_phrase();
+        }
+        if self.config.canonical_header_names {
+            response.canonicalize_header_names();
+        }
+        response.set_trailers_accepted(request.accepts_trailers());
+        // HTTP/1.0 has no chunked coding, so a body of unknown length can
+        // only be ended by closing the connection.
+        let close_delimited = matches!(request.get_http_version(), HttpVersion::Http1_0)
+            && response.delimit_by_close();
+        let keep_alive = !close_delimited
+            && remaining > 0
+            && !self.connections.is_shutting_down()
+            && request.wants_keep_alive()
+            && (self.config.keep_alive_http1_0
+                || !matches!(request.get_http_version(), HttpVersion::Http1_0))
+            && !response
+                .get_header("Connection")
+                .is_some_and(|value| value.eq_ignore_ascii_case("close"));
+        if keep_alive {
+            response.add_header("Connection", "keep-alive").add_header(
+                "Keep-Alive",
+                &format!(
+                    "timeout={}, max={}",
+                    self.config.idle_timeout.as_secs(),
+                    remaining
+                ),
+            );
+        } else if response.get_header("Connection").is_none() {
+            response.add_header("Connection", "close");
+        }
+
+        self.stats.response_sent(response.get_status_code());
+        let written = match request.get_method() {
+            HttpRequestMethod::Head => response.write_head_to(connection),
+            _ => response.write_to_connection(connection),
+        };
+        if let Err(e) = written {
+            println!("error: {}", e);
+            return false;
+        }
+        keep_alive
+    }
+
+    /// Fills an empty error response with the configured page for its
+    /// status, returning whether there was one.
+    fn apply_error_page(&self, response: &mut HttpResponse) -> bool {
+        let status_code = response.get_status_code();
+        if status_code < 400 || response.has_body() {
+            return false;
+        }
+        let Some(page) = self.config.error_pages.get(&status_code) else {
+            return false;
+        };
+        let body = page.render(status_code, response.get_reason());
+        response
+            .add_header("Content-Type", &page.content_type)
+            .add_content(&body);
+        true
+    }
+
+    /// Produces the response to `request`, and whether it is to be access
+    /// logged.
+    fn handle_request(
+        &self,
+        request: &HttpRequest,
+        found: Option<(&Route, Params)>,
+        rejected: Option<HttpResponse>,
+        peer_addr: Option<SocketAddr>,
+    ) -> (HttpResponse, bool) {
+        let early = rejected
+            .or_else(|| {
+                self.config
+                    .upgrade_insecure_requests
+                    .then(|| redirect::upgrade_insecure(request))
+                    .flatten()
+            })
+            .or_else(|| self.assets.serve(request));
+        match (early, found) {
+            (Some(response), _) => (response, true),
+            (None, Some((route, params))) => {
+                let mut response = run_handler(
+                    route,
+                    request,
+                    params,
+                    peer_addr,
+                    self.request_ids.fetch_add(1, Ordering::Relaxed) + 1,
+                    self.sessions.as_ref(),
+                );
+                if route.is_conditional_get() {
+                    conditional::revalidate(request, &mut response);
+                }
+                (response, route.is_access_logged())
+            }
+            (None, None) => (
+                HttpResponse::from_status(
+                    request.get_http_version().to_owned(),
+                    HttpStatus::NotFound,
+                ),
+                true,
+            ),
+        }
+    }
+}
+
+/// Runs the route's handler, on a separate thread when the request has a
+/// deadline so that the wait can be abandoned.
+fn run_handler(
+    route: &Route,
+    request: &HttpRequest,
+    params: Params,
+    peer_addr: Option<SocketAddr>,
+    request_id: u64,
+    sessions: Option<&Arc<Sessions>>,
+) -> HttpResponse {
+    // Runs the handler between loading the client's session and saving it.
+    let call = move |handler: &Handler, request: &HttpRequest, sessions: Option<&Sessions>| {
+        let session = sessions.map(|sessions| sessions.load(request));
+        let mut response = handler(&RequestContext {
+            request,
+            params: &params,
+            peer_addr,
+            request_id,
+            session: session.as_ref(),
+        });
+        if let (Some(sessions), Some(session)) = (sessions, session) {
+            if let Err(e) = sessions.save(session, &mut response) {
+                println!("error saving session: {}", e);
+                response = HttpResponse::from_status(
+                    request.get_http_version().to_owned(),
+                    HttpStatus::InternalServerError,
+                );
+            }
+        }
+        response
+    };
+    let Some(timeout) = request.time_remaining() else {
+        return call(route.get_handler(), request, sessions.map(Arc::as_ref));
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let handler = Arc::clone(route.get_handler());
+    let owned_request = request.clone();
+    let sessions = sessions.cloned();
+    thread::spawn(move || {
+        let response = call(&handler, &owned_request, sessions.as_deref());
+        // Release the request, and any handle on the connection it holds,
+        // before the server is told the handler is done.
+        drop(owned_request);
+        // The receiver is gone if the timeout has already passed.
+        let _ = sender.send(response);
+    });
+
+    receiver.recv_timeout(timeout).unwrap_or_else(|e| {
+        let status = match e {
+            RecvTimeoutError::Timeout => HttpStatus::GatewayTimeout,
+            // The handler panicked before sending a response.
+            RecvTimeoutError::Disconnected => HttpStatus::InternalServerError,
+        };
+        HttpResponse::from_status(request.get_http_version().to_owned(), status)
+    })
+}
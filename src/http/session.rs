@@ -0,0 +1,177 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex, MutexGuard, PoisonError,
+    },
+};
+
+use super::{request::HttpRequest, response::HttpResponse};
+
+pub type SessionData = HashMap<String, String>;
+
+/// Where sessions are kept between requests, by session id.
+pub trait SessionStore: Send + Sync {
+    fn get(&self, id: &str) -> Option<SessionData>;
+
+    fn set(&self, id: &str, data: SessionData);
+}
+
+/// Keeps sessions in memory, so they last as long as the server runs.
+#[derive(Default)]
+pub struct MemoryStore {
+    sessions: Mutex<HashMap<String, SessionData>>,
+}
+
+impl SessionStore for MemoryStore {
+    fn get(&self, id: &str) -> Option<SessionData> {
+        let sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        sessions.get(id).cloned()
+    }
+
+    fn set(&self, id: &str, data: SessionData) {
+        let mut sessions = self.sessions.lock().unwrap_or_else(PoisonError::into_inner);
+        sessions.insert(id.to_string(), data);
+    }
+}
+
+/// The session of the client making a request, loaded from the store by
+/// its cookie. Once the handler returns, a session it changed is saved and
+/// its cookie sent again, which starts a new session for a client that had
+/// none.
+///
+/// ```
+/// use std::{
+///     io::{Read, Write},
+///     net::TcpStream,
+/// };
+///
+/// use http_server_starter_rust::http::{
+///     client::HttpClient, config::ServerConfig, response::HttpResponse, server::Server,
+///     session::MemoryStore,
+/// };
+///
+/// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+/// server.sessions("sid", MemoryStore::default());
+/// server.get("/visit", |context| {
+///     let session = context.session.unwrap();
+///     let visits: u32 = session.get("visits").map_or(0, |visits| visits.parse().unwrap());
+///     session.insert("visits", &(visits + 1).to_string());
+///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+///     response.write_text(&format!("visit {}", visits + 1));
+///     response
+/// });
+/// let addr = server.local_addr()?;
+/// std::thread::spawn(move || server.run());
+///
+/// let first = HttpClient::get(&format!("http://{addr}/visit")).unwrap();
+/// assert_eq!(first.get_body(), b"visit 1");
+/// let cookie = first.get_header("Set-Cookie").unwrap();
+/// assert!(cookie.starts_with("sid="));
+/// assert!(cookie.contains("; HttpOnly"));
+///
+/// let session_cookie = cookie.split(';').next().unwrap();
+/// let mut stream = TcpStream::connect(addr)?;
+/// write!(
+///     stream,
+///     "GET /visit HTTP/1.1\r\nCookie: theme=dark; {session_cookie}\r\nConnection: close\r\n\r\n"
+/// )?;
+/// let mut second = String::new();
+/// stream.read_to_string(&mut second)?;
+/// assert!(second.contains(&format!("\r\nSet-Cookie: {cookie}\r\n")));
+/// assert!(second.ends_with("\r\n\r\nvisit 2"));
+///
+/// let fresh = HttpClient::get(&format!("http://{addr}/visit")).unwrap();
+/// assert_eq!(fresh.get_body(), b"visit 1");
+/// assert_ne!(fresh.get_header("Set-Cookie").unwrap(), cookie);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct Session {
+    /// The id from the client's cookie, if it named a stored session.
+    id: Option<String>,
+    data: Mutex<SessionData>,
+    modified: AtomicBool,
+}
+
+impl Session {
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.lock().get(key).cloned()
+    }
+
+    pub fn insert(&self, key: &str, value: &str) {
+        self.lock().insert(key.to_string(), value.to_string());
+        self.mark_modified();
+    }
+
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let removed = self.lock().remove(key);
+        if removed.is_some() {
+            self.mark_modified();
+        }
+        removed
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SessionData> {
+        self.data.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn mark_modified(&self) {
+        self.modified.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A session store together with the cookie that names a client's
+/// session in it.
+pub(crate) struct Sessions {
+    pub(crate) cookie: String,
+    pub(crate) store: Box<dyn SessionStore>,
+}
+
+impl Sessions {
+    pub(crate) fn load(&self, request: &HttpRequest) -> Session {
+        let stored = request.get_cookie(&self.cookie).and_then(|id| {
+            let data = self.store.get(id)?;
+            Some((id.to_string(), data))
+        });
+        let (id, data) = match stored {
+            Some((id, data)) => (Some(id), data),
+            None => (None, SessionData::new()),
+        };
+        Session {
+            id,
+            data: Mutex::new(data),
+            modified: AtomicBool::new(false),
+        }
+    }
+
+    /// Saves `session` if the handler changed it, giving it an id if it is
+    /// new, and adds its cookie to `response`. Fails, leaving the session
+    /// unsaved, if no id can be generated for it.
+    pub(crate) fn save(&self, session: Session, response: &mut HttpResponse) -> io::Result<()> {
+        if !session.modified.into_inner() {
+            return Ok(());
+        }
+        let data = session
+            .data
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner);
+        let id = session.id.map_or_else(random_token, Ok)?;
+        self.store.set(&id, data);
+        response.add_header(
+            "Set-Cookie",
+            &format!("{}={}; Path=/; HttpOnly; SameSite=Lax", self.cookie, id),
+        );
+        Ok(())
+    }
+}
+
+/// 128 random bits in hex, for session ids and other tokens, read from the
+/// operating system's CSPRNG so that they cannot be guessed. Fails rather
+/// than falling back to a weaker source.
+pub(crate) fn random_token() -> io::Result<String> {
+    let mut bytes = [0; 16];
+    File::open("/dev/urandom")?.read_exact(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
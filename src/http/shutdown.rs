@@ -0,0 +1,185 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use super::listener::Accepted;
+
+/// The connections a server has open, kept so that it can stop taking new
+/// ones and close the rest when it shuts down.
+#[derive(Default)]
+pub(crate) struct Tracker {
+    state: Mutex<State>,
+    drained: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    shutting_down: bool,
+    next_id: u64,
+    connections: HashMap<u64, Tracked>,
+}
+
+struct Tracked {
+    /// Closes a clone of the connection's stream.
+    close: Box<dyn Fn() + Send>,
+    /// Whether the connection is waiting for another request, with none in
+    /// flight.
+    idle: bool,
+}
+
+impl Tracker {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub(crate) fn is_shutting_down(&self) -> bool {
+        self.lock().shutting_down
+    }
+
+    /// Tracks a connection through a clone of its stream until the returned
+    /// guard is dropped.
+    pub(crate) fn track<S: Accepted>(self: &Arc<Self>, stream: S) -> TrackedConnection {
+        let mut state = self.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        state.connections.insert(
+            id,
+            Tracked {
+                close: Box::new(move || stream.shutdown()),
+                idle: false,
+            },
+        );
+        TrackedConnection {
+            tracker: Arc::clone(self),
+            id,
+        }
+    }
+}
+
+pub(crate) struct TrackedConnection {
+    tracker: Arc<Tracker>,
+    id: u64,
+}
+
+impl TrackedConnection {
+    /// Marks the connection as waiting for its next request, returning
+    /// `false` if the server is shutting down and it should be closed
+    /// instead.
+    pub(crate) fn set_idle(&self) -> bool {
+        self.set_state(true)
+    }
+
+    /// Marks the connection as serving a request.
+    pub(crate) fn set_busy(&self) {
+        self.set_state(false);
+    }
+
+    fn set_state(&self, idle: bool) -> bool {
+        let mut state = self.tracker.lock();
+        if idle && state.shutting_down {
+            return false;
+        }
+        if let Some(tracked) = state.connections.get_mut(&self.id) {
+            tracked.idle = idle;
+        }
+        true
+    }
+}
+
+impl Drop for TrackedConnection {
+    fn drop(&mut self) {
+        self.tracker.lock().connections.remove(&self.id);
+        self.tracker.drained.notify_all();
+    }
+}
+
+/// Shuts down a running server from another thread; see
+/// [`Server::shutdown_handle`](super::server::Server::shutdown_handle).
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    pub(crate) tracker: Arc<Tracker>,
+    /// Connects to the server's listener.
+    pub(crate) wake: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl ShutdownHandle {
+    /// Stops accepting connections and gives those in flight up to
+    /// `timeout` to finish, returning whether they all did. Connections
+    /// waiting idle for another request are closed straight away, and any
+    /// still open once `timeout` has passed are closed as well.
+    ///
+    /// Closing a connection cannot stop a handler that is still running;
+    /// its response is just not delivered.
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    ///     time::Duration,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// // Starts a server whose only handler takes `delay` to respond, sends
+    /// // it a request and shuts it down with `grace` while it is in flight.
+    /// let shut_down = |delay: u64, grace: u64| -> std::io::Result<(bool, String)> {
+    ///     let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    ///     server.get("/slow", move |context| {
+    ///         thread::sleep(Duration::from_millis(delay));
+    ///         HttpResponse::new(context.request.get_http_version().to_owned())
+    ///     });
+    ///     let addr = server.local_addr()?;
+    ///     let stats = server.stats_handle();
+    ///     let handle = server.shutdown_handle()?;
+    ///     thread::spawn(move || server.run());
+    ///
+    ///     let mut stream = TcpStream::connect(addr)?;
+    ///     stream.write_all(b"GET /slow HTTP/1.1\r\n\r\n")?;
+    ///     while stats.snapshot().active_connections == 0 {
+    ///         thread::sleep(Duration::from_millis(5));
+    ///     }
+    ///     let drained = handle.shutdown(Duration::from_millis(grace));
+    ///     let mut received = String::new();
+    ///     let _ = stream.read_to_string(&mut received);
+    ///     Ok((drained, received))
+    /// };
+    ///
+    /// let (drained, received) = shut_down(100, 5_000)?;
+    /// assert!(drained);
+    /// assert!(received.starts_with("HTTP/1.1 200 OK\r\n"));
+    /// assert!(received.contains("Connection: close\r\n"));
+    ///
+    /// let (drained, received) = shut_down(1_000, 50)?;
+    /// assert!(!drained);
+    /// assert!(received.is_empty());
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn shutdown(&self, timeout: Duration) -> bool {
+        {
+            let mut state = self.tracker.lock();
+            state.shutting_down = true;
+            for tracked in state.connections.values().filter(|tracked| tracked.idle) {
+                (tracked.close)();
+            }
+        }
+        // The accept loop only notices the shutdown once it accepts
+        // another connection.
+        (self.wake)();
+
+        let state = self.tracker.lock();
+        let (state, _) = self
+            .tracker
+            .drained
+            .wait_timeout_while(state, timeout, |state| !state.connections.is_empty())
+            .unwrap_or_else(PoisonError::into_inner);
+        for tracked in state.connections.values() {
+            (tracked.close)();
+        }
+        state.connections.is_empty()
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Status codes from `100` to `599` are counted individually.
+const STATUS_CODES: std::ops::Range<u16> = 100..600;
+
+/// Counters the server updates as it accepts connections and answers
+/// requests. They are plain atomics, so reading them never blocks a worker.
+pub struct Stats {
+    connections_accepted: AtomicU64,
+    active_connections: AtomicU64,
+    requests_handled: AtomicU64,
+    /// Responses by status code, indexed from `100`.
+    responses: Vec<AtomicU64>,
+}
+
+/// A point-in-time copy of a server's [`Stats`].
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ServerStats {
+    pub connections_accepted: u64,
+    pub active_connections: u64,
+    pub requests_handled: u64,
+    pub responses_1xx: u64,
+    pub responses_2xx: u64,
+    pub responses_3xx: u64,
+    pub responses_4xx: u64,
+    pub responses_5xx: u64,
+    /// The status codes that have been sent at least once, in ascending
+    /// order, with how many times each was sent.
+    pub responses_by_status: Vec<(u16, u64)>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            connections_accepted: AtomicU64::default(),
+            active_connections: AtomicU64::default(),
+            requests_handled: AtomicU64::default(),
+            responses: STATUS_CODES.map(|_| AtomicU64::default()).collect(),
+        }
+    }
+}
+
+impl Stats {
+    pub fn snapshot(&self) -> ServerStats {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let responses_by_status: Vec<(u16, u64)> = STATUS_CODES
+            .zip(&self.responses)
+            .map(|(status_code, counter)| (status_code, load(counter)))
+            .filter(|&(_, count)| count > 0)
+            .collect();
+        let class = |class: u16| {
+            responses_by_status
+                .iter()
+                .filter(|(status_code, _)| status_code / 100 == class)
+                .map(|(_, count)| count)
+                .sum()
+        };
+        ServerStats {
+            connections_accepted: load(&self.connections_accepted),
+            active_connections: load(&self.active_connections),
+            requests_handled: load(&self.requests_handled),
+            responses_1xx: class(1),
+            responses_2xx: class(2),
+            responses_3xx: class(3),
+            responses_4xx: class(4),
+            responses_5xx: class(5),
+            responses_by_status,
+        }
+    }
+
+    pub(crate) fn connection_accepted(&self) {
+        self.connections_accepted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a connection as active until the returned guard is dropped.
+    pub(crate) fn connection_opened(&self) -> ActiveConnection<'_> {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+        ActiveConnection(self)
+    }
+
+    /// Counts a request whose response status is not known, such as one
+    /// relayed by the proxy.
+    pub(crate) fn request_handled(&self) {
+        self.requests_handled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn response_sent(&self, status_code: u16) {
+        self.request_handled();
+        if let Some(counter) = status_code
+            .checked_sub(STATUS_CODES.start)
+            .and_then(|index| self.responses.get(index as usize))
+        {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+pub(crate) struct ActiveConnection<'a>(&'a Stats);
+
+impl Drop for ActiveConnection<'_> {
+    fn drop(&mut self) {
+        self.0.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
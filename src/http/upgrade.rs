@@ -0,0 +1,80 @@
+use std::{
+    io::{self, Read, Write},
+    sync::Arc,
+};
+
+use super::{
+    request::HttpRequest,
+    response::{HttpResponse, HttpStatus},
+    HttpVersion,
+};
+
+/// A connection switched to another protocol, readable and writable in
+/// whatever way that protocol defines.
+pub trait Stream: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Stream for T {}
+
+pub type UpgradeHandler = Arc<dyn Fn(&HttpRequest, Upgraded) + Send + Sync>;
+
+/// A request to switch protocols that an upgrade handler has been given,
+/// holding the `101 Switching Protocols` response it will be answered
+/// with and the connection it came in on.
+///
+/// Dropping it without calling [`accept`](Self::accept) closes the
+/// connection without a response.
+pub struct Upgraded {
+    response: HttpResponse,
+    stream: Box<dyn Stream>,
+}
+
+impl Upgraded {
+    pub(crate) fn new(protocol: &str, stream: Box<dyn Stream>) -> Self {
+        let mut response =
+            HttpResponse::from_status(HttpVersion::Http1_1, HttpStatus::SwitchingProtocols);
+        response
+            .add_header("Connection", "Upgrade")
+            .add_header("Upgrade", protocol);
+        Self { response, stream }
+    }
+
+    /// The `101` response, for adding headers the protocol's handshake
+    /// calls for.
+    pub fn response(&mut self) -> &mut HttpResponse {
+        &mut self.response
+    }
+
+    /// Sends the `101` response, and hands over the connection for the new
+    /// protocol. Anything the client sent after the request is still there
+    /// to be read.
+    pub fn accept(mut self) -> io::Result<Box<dyn Stream>> {
+        self.response.write_head_to(&mut self.stream)?;
+        Ok(self.stream)
+    }
+}
+
+/// The protocol to switch `request` to: the first one its `Upgrade` header
+/// lists that is in `protocols`, compared case-insensitively. Only HTTP/1.1
+/// requests that list `upgrade` among their connection options qualify.
+pub(crate) fn requested_protocol<'a, T>(
+    request: &HttpRequest,
+    protocols: &'a [(String, T)],
+) -> Option<&'a (String, T)> {
+    if !matches!(request.get_http_version(), HttpVersion::Http1_1)
+        || !request
+            .connection_options()
+            .iter()
+            .any(|option| option == "upgrade")
+    {
+        return None;
+    }
+    request
+        .get_header("Upgrade")?
+        .split(',')
+        .map(str::trim)
+        .find_map(|offered| {
+            protocols
+                .iter()
+                .find(|(protocol, _)| protocol.eq_ignore_ascii_case(offered))
+        })
+}
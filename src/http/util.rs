@@ -0,0 +1,24 @@
+/// Compares `a` and `b` without stopping at the first differing byte, so
+/// that how long the comparison takes does not reveal how much of a
+/// guessed secret was right. Only the lengths, which are usually not
+/// secret, affect the timing.
+///
+/// ```
+/// use http_server_starter_rust::http::util::constant_time_eq;
+///
+/// assert!(constant_time_eq(b"s3cret", b"s3cret"));
+/// assert!(!constant_time_eq(b"s3cret", b"s3creT"));
+/// assert!(!constant_time_eq(b"s3cret", b"s3cre"));
+/// assert!(constant_time_eq(b"", b""));
+/// ```
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let difference = a
+        .iter()
+        .zip(b)
+        .fold(0, |difference, (x, y)| difference | (x ^ y));
+    // Keeps the compiler from turning the fold back into an early exit.
+    std::hint::black_box(difference) == 0
+}
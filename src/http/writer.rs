@@ -0,0 +1,210 @@
+use std::io::{self, Write};
+
+/// Writes a response body using chunked transfer coding.
+///
+/// Each `write` call becomes one chunk. Chunks are buffered until
+/// [`flush`](Write::flush) is called or the buffer fills up, so many small
+/// writes cost few syscalls; use [`send_chunk`](Self::send_chunk) when a
+/// chunk has to reach the client right away, as with server-sent events.
+///
+/// HTTP/1.0 clients do not understand chunked coding, so for them the body
+/// is written as is and ends when the server closes the connection.
+pub struct ChunkedWriter<'a> {
+    inner: &'a mut dyn Write,
+    trailers: Vec<(String, String)>,
+    trailers_accepted: bool,
+    close_delimited: bool,
+}
+
+impl<'a> ChunkedWriter<'a> {
+    pub(crate) fn new(inner: &'a mut dyn Write, trailers_accepted: bool) -> Self {
+        Self {
+            inner,
+            trailers: Vec::new(),
+            trailers_accepted,
+            close_delimited: false,
+        }
+    }
+
+    /// Writes the body without any framing, for a response whose end is
+    /// marked by closing the connection.
+    pub(crate) fn close_delimited(inner: &'a mut dyn Write) -> Self {
+        Self {
+            close_delimited: true,
+            ..Self::new(inner, false)
+        }
+    }
+
+    /// Adds a header to send after the last chunk, for a value only known
+    /// once the body is written, such as a checksum. Trailers are only sent
+    /// to clients that accept them; see
+    /// [`trailers_accepted`](Self::trailers_accepted).
+    ///
+    /// ```
+    /// use std::{
+    ///     io::{Read, Write},
+    ///     net::TcpStream,
+    ///     thread,
+    /// };
+    ///
+    /// use http_server_starter_rust::http::{
+    ///     config::ServerConfig, response::HttpResponse, server::Server,
+    /// };
+    ///
+    /// let mut server = Server::bind("127.0.0.1:0", ServerConfig::default())?;
+    /// server.get("/", |context| {
+    ///     let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+    ///     response.add_header("Trailer", "X-Checksum").stream_chunked(|writer| {
+    ///         writer.write_all(b"hello")?;
+    ///         writer.add_trailer("X-Checksum", "532");
+    ///         Ok(())
+    ///     });
+    ///     response
+    /// });
+    /// let addr = server.local_addr()?;
+    /// thread::spawn(move || server.run());
+    ///
+    /// let get = |headers: &str| -> std::io::Result<String> {
+    ///     let mut stream = TcpStream::connect(addr)?;
+    ///     write!(stream, "GET / HTTP/1.1\r\n{headers}Connection: close\r\n\r\n")?;
+    ///     let mut received = String::new();
+    ///     stream.read_to_string(&mut received)?;
+    ///     Ok(received)
+    /// };
+    ///
+    /// let with = get("TE: trailers\r\n")?;
+    /// assert!(with.ends_with("\r\n\r\n5\r\nhello\r\n0\r\nX-Checksum: 532\r\n\r\n"));
+    /// let without = get("")?;
+    /// assert!(without.ends_with("\r\n\r\n5\r\nhello\r\n0\r\n\r\n"));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn add_trailer(&mut self, name: &str, value: &str) -> &mut Self {
+        let strip = |text: &str| text.replace(['\r', '\n'], "");
+        self.trailers.push((strip(name), strip(value)));
+        self
+    }
+
+    /// Whether the client sent `TE: trailers`, saying it can handle
+    /// trailers. Without it, added trailers are dropped, since a client
+    /// is not required to understand them.
+    pub fn trailers_accepted(&self) -> bool {
+        self.trailers_accepted
+    }
+
+    pub fn send_chunk(&mut self, chunk: &[u8]) -> io::Result<()> {
+        self.write_all(chunk)?;
+        self.flush()
+    }
+
+    pub(crate) fn finish(self) -> io::Result<()> {
+        if self.close_delimited {
+            return self.inner.flush();
+        }
+        self.inner.write_all(b"0\r\n")?;
+        if self.trailers_accepted {
+            for (name, value) in &self.trailers {
+                write!(self.inner, "{name}: {value}\r\n")?;
+            }
+        }
+        self.inner.write_all(b"\r\n")?;
+        self.inner.flush()
+    }
+}
+
+impl Write for ChunkedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // An empty chunk would mark the end of the body.
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.close_delimited {
+            return self.inner.write(buf);
+        }
+        write!(self.inner, "{:x}\r\n", buf.len())?;
+        self.inner.write_all(buf)?;
+        self.inner.write_all(b"\r\n")?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Buffers a body of unknown length behind a response head that has not
+/// been sent yet. The head goes out with a `Content-Length` if the body
+/// ends within `threshold` bytes, and with chunked transfer coding as soon
+/// as it grows past them.
+pub(crate) struct BufferedBodyWriter<'a> {
+    inner: &'a mut dyn Write,
+    /// The status line and headers, still missing the framing header.
+    head: String,
+    threshold: usize,
+    buffer: Vec<u8>,
+    chunked: bool,
+}
+
+impl<'a> BufferedBodyWriter<'a> {
+    pub(crate) fn new(inner: &'a mut dyn Write, head: String, threshold: usize) -> Self {
+        Self {
+            inner,
+            head,
+            threshold,
+            buffer: Vec::new(),
+            chunked: false,
+        }
+    }
+
+    /// Sends the buffer as one chunk, sending the head first if the body
+    /// has not switched to chunked coding yet.
+    fn send_chunk(&mut self) -> io::Result<()> {
+        if !self.chunked {
+            write!(
+                self.inner,
+                "{}Transfer-Encoding: chunked\r\n\r\n",
+                self.head
+            )?;
+            self.chunked = true;
+        }
+        ChunkedWriter::new(self.inner, false).write_all(&self.buffer)?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> io::Result<()> {
+        if self.chunked {
+            self.send_chunk()?;
+            return ChunkedWriter::new(self.inner, false).finish();
+        }
+        write!(
+            self.inner,
+            "{}Content-Length: {}\r\n\r\n",
+            self.head,
+            self.buffer.len()
+        )?;
+        self.inner.write_all(&self.buffer)?;
+        self.inner.flush()
+    }
+}
+
+impl Write for BufferedBodyWriter<'_> {
+    /// Writes are coalesced up to the threshold in chunked mode too, so a
+    /// body written in small pieces is not sent as many tiny chunks.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        if self.buffer.len() > self.threshold {
+            self.send_chunk()?;
+        }
+        Ok(buf.len())
+    }
+
+    /// Nothing can be sent while the framing is undecided, so flushing only
+    /// takes effect once the body has switched to chunked coding.
+    fn flush(&mut self) -> io::Result<()> {
+        if self.chunked {
+            self.send_chunk()?;
+            self.inner.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -1,52 +1,51 @@
-use std::io::Write;
-use std::net::{TcpListener, TcpStream};
-use std::thread;
+use std::env;
 
-use http::response::HttpStatus;
-
-use crate::http::request::HttpRequest;
-use crate::http::response::HttpResponse;
-
-mod http;
+use http_server_starter_rust::http::config::ServerConfig;
+use http_server_starter_rust::http::files::FileHandler;
+use http_server_starter_rust::http::proxy::ReverseProxy;
+use http_server_starter_rust::http::request::HttpRequestMethod;
+use http_server_starter_rust::http::response::HttpResponse;
+use http_server_starter_rust::http::server::Server;
 
 fn main() {
-    let listener = TcpListener::bind("127.0.0.1:4221").unwrap();
+    let mut server = Server::bind("127.0.0.1:4221", ServerConfig::default()).unwrap();
+    if let Some(upstream) = arg_value("--upstream") {
+        server.set_proxy(ReverseProxy::new(&upstream));
+    }
 
-    for stream in listener.incoming() {
-        match stream {
-            Ok(stream) => {
-                thread::spawn(|| {
-                    handle_connection(stream);
-                });
-            }
-            Err(e) => {
-                println!("error: {}", e);
-            }
+    let router = server.router();
+    router.add(None, "/", |context| {
+        HttpResponse::new(context.request.get_http_version().to_owned())
+    });
+    router.add(None, "/user-agent", |context| {
+        let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+        response.write_text(context.request.get_header("User-Agent").unwrap_or_default());
+        response
+    });
+    router.add(None, "/echo/*", |context| {
+        let mut response = HttpResponse::new(context.request.get_http_version().to_owned());
+        response.write_text(context.params.get("*").unwrap_or_default());
+        response
+    });
+    if let Some(directory) = arg_value("--directory") {
+        let files = FileHandler::new(directory);
+        for method in [HttpRequestMethod::Get, HttpRequestMethod::Head] {
+            let files = files.clone();
+            router.add(Some(method), "/files/*", move |context| {
+                files.serve(context.request, context.params.get("*").unwrap_or_default())
+            });
         }
     }
-}
 
-fn handle_connection(mut stream: TcpStream) {
-    println!("accepted new connection");
-    let request = HttpRequest::from_stream(&stream).expect("Failed to parse the request.");
-    let response = handle_request(request);
-    stream.write_all(response.to_string().as_bytes()).unwrap();
+    server.run();
 }
 
-fn handle_request(request: HttpRequest) -> HttpResponse {
-    let mut response = HttpResponse::new(request.get_http_version().to_owned());
-
-    match request.get_path() {
-        "/" => {}
-        "/user-agent" => {
-            response.write_text(request.get_header("User-Agent").unwrap());
-        }
-        path if path.starts_with("/echo/") => {
-            response.write_text(path.trim_start_matches("/echo/"));
-        }
-        _ => {
-            response.set_status(HttpStatus::NotFound);
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
         }
     }
-    response
+    None
 }